@@ -1,25 +1,75 @@
+extern crate self as web_framework_lib;
 
+#[doc(hidden)]
+pub use inventory;
 
+pub use proc_macros_derive::{delete, get, head, options, patch, post, put};
 pub mod web {
+    pub mod middleware {
+        pub mod access_log_middleware;
+        pub mod auth_middleware;
+        pub mod compression_middleware;
+        pub mod conditional_middleware;
+        pub mod cors_middleware;
+        pub mod csrf_middleware;
+        pub mod group_middleware;
+        pub mod middleware_trait;
+        pub mod rate_limit_middleware;
+        pub mod security_headers_middleware;
+        pub mod session_middleware;
+    }
+
     pub mod models {
+        pub mod body;
+        pub mod cookie;
+        pub mod cookie_jar;
+        pub mod etag;
+        pub mod header_map;
+        pub mod problem;
         pub mod request;
         pub mod response;
+        pub mod status_code;
     }
 
     pub mod request_handling {
+        pub mod fallback_handlers;
+        pub mod metrics;
         pub mod request_handler;
+        pub mod route;
+        pub mod route_definition;
         pub mod route_handler_container;
+        pub mod static_files;
+        pub mod timeouts;
+    }
+    pub mod sessions {
+        pub mod file_session_store;
+        pub mod memory_session_store;
+        pub mod session;
+        pub mod session_store;
+    }
+    pub mod templating {
+        pub mod template;
+        pub mod template_engine;
     }
     pub mod util {
         pub mod encoders {
+            pub mod hmac_signer;
+            pub mod html_encoder;
             pub mod url_encoder;
         }
         pub mod enums {
             pub mod http_method_enum;
             pub mod mime_types;
+            pub mod redirect_kind;
         }
         pub mod parsers {
+            pub mod cookie_parser;
+            pub mod form_parser;
+            pub mod http_date_parser;
             pub mod json_parser;
+            pub mod path_parser;
+            pub mod quality_value_parser;
+            pub mod range_parser;
             pub mod request_parser;
         }
     }
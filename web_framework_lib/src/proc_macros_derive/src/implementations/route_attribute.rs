@@ -0,0 +1,131 @@
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ItemFn, LitStr};
+
+/// It takes the arguments and the item of a route attribute, e.g. `#[get("/users/{id}")]`, and
+/// returns the function untouched, followed by a submission of its `RouteDefinition`, so
+/// `RouteHandlerContainer::insert_annotated_routes` can find it.
+///
+/// Arguments:
+///
+/// * `method`: The name of the `HttpMethod` variant, e.g. "GET".
+/// * `args`: The tokens between the parentheses, which have to be a single string literal.
+/// * `input`: The annotated item, which has to be a function.
+///
+/// Returns:
+///
+/// A TokenStream, or a compile error pointing at whatever was wrong.
+pub fn impl_route_attribute(method: &str, args: TokenStream, input: TokenStream) -> TokenStream {
+    let path: LitStr = match syn::parse::<LitStr>(args) {
+        Ok(path) => path,
+        Err(e) => {
+            return syn::Error::new(e.span(), "expected a path, e.g. #[get(\"/users/{id}\")]")
+                .to_compile_error()
+                .into();
+        }
+    };
+    if let Err(message) = validate_path_pattern(&path.value()) {
+        return syn::Error::new(path.span(), message).to_compile_error().into();
+    }
+    let item_fn: ItemFn = match syn::parse::<ItemFn>(input) {
+        Ok(item_fn) => item_fn,
+        Err(e) => {
+            return syn::Error::new(e.span(), "route attributes can only be used on functions")
+                .to_compile_error()
+                .into();
+        }
+    };
+    let fn_ident: &syn::Ident = &item_fn.sig.ident;
+    let method_ident: syn::Ident = format_ident!("{}", method);
+    let quote: proc_macro2::TokenStream = quote! {
+        #item_fn
+
+        ::web_framework_lib::inventory::submit! {
+            ::web_framework_lib::web::request_handling::route_definition::RouteDefinition::new(
+                #path,
+                #fn_ident as ::web_framework_lib::web::server::HandlerFunction,
+                ::web_framework_lib::web::util::enums::http_method_enum::HttpMethod::#method_ident
+            )
+        }
+    };
+    TokenStream::from(quote)
+}
+
+/// It checks a path pattern the same way `RouteHandlerContainer::insert` reads it, so mistakes
+/// show up at compile time instead of as a panic when the routes are inserted.
+///
+/// Arguments:
+///
+/// * `path`: The path pattern, e.g. "/users/{id}".
+///
+/// Returns:
+///
+/// Ok if the pattern is valid, otherwise a message describing the problem.
+pub fn validate_path_pattern(path: &str) -> Result<(), String> {
+    if !path.starts_with('/') {
+        return Err(format!("path \"{}\" has to start with '/'", path));
+    }
+    if let Some(c) = path.chars().find(|c: &char| c.is_whitespace() || *c == '?' || *c == '#') {
+        return Err(format!("path \"{}\" contains the invalid character {:?}", path, c));
+    }
+    let mut open_index: Option<usize> = None;
+    for (i, c) in path.char_indices() {
+        match (c, open_index) {
+            ('{', Some(_)) => {
+                return Err(format!("nested '{{' at position {} in path \"{}\"", i, path));
+            },
+            ('{', None) => { open_index = Some(i); },
+            ('}', None) => {
+                return Err(format!("unmatched '}}' at position {} in path \"{}\"", i, path));
+            },
+            ('}', Some(open)) => {
+                let name: &str = &path[open + 1..i];
+                if name.is_empty() {
+                    return Err(format!("empty path variable at position {} in path \"{}\"", open, path));
+                }
+                if !name.chars().all(|c: char| c.is_alphanumeric() || c == '_') {
+                    return Err(format!("invalid path variable name \"{}\" in path \"{}\"", name, path));
+                }
+                open_index = None;
+            },
+            ('/', Some(open)) => {
+                return Err(format!("unclosed '{{' at position {} in path \"{}\"", open, path));
+            },
+            _ => {}
+        }
+    }
+    match open_index {
+        Some(open) => Err(format!("unclosed '{{' at position {} in path \"{}\"", open, path)),
+        None => Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::implementations::route_attribute::validate_path_pattern;
+
+    #[test]
+    fn accepts_valid_patterns() {
+        assert_eq!(validate_path_pattern("/"), Ok(()));
+        assert_eq!(validate_path_pattern("/users/{id}/posts/{post_id}"), Ok(()));
+        assert_eq!(validate_path_pattern("/files/{name}.txt"), Ok(()));
+        assert_eq!(validate_path_pattern("/a(b)+[c]*|d"), Ok(()));
+    }
+
+    #[test]
+    fn reports_invalid_patterns() {
+        assert_eq!(validate_path_pattern("users"), Err("path \"users\" has to start with '/'".to_string()));
+        assert_eq!(validate_path_pattern("/a b"),
+                   Err("path \"/a b\" contains the invalid character ' '".to_string()));
+        assert_eq!(validate_path_pattern("/a?b=c"),
+                   Err("path \"/a?b=c\" contains the invalid character '?'".to_string()));
+        assert_eq!(validate_path_pattern("/{a{b}}"),
+                   Err("nested '{' at position 3 in path \"/{a{b}}\"".to_string()));
+        assert_eq!(validate_path_pattern("/a}"), Err("unmatched '}' at position 2 in path \"/a}\"".to_string()));
+        assert_eq!(validate_path_pattern("/{}"), Err("empty path variable at position 1 in path \"/{}\"".to_string()));
+        assert_eq!(validate_path_pattern("/{a-b}"),
+                   Err("invalid path variable name \"a-b\" in path \"/{a-b}\"".to_string()));
+        assert_eq!(validate_path_pattern("/{id/x}"), Err("unclosed '{' at position 1 in path \"/{id/x}\"".to_string()));
+        assert_eq!(validate_path_pattern("/{id"), Err("unclosed '{' at position 1 in path \"/{id\"".to_string()));
+    }
+}
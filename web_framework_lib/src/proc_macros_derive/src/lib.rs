@@ -2,11 +2,12 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 
-use crate::implementations::{enum_from_str, str_from_enum};
+use crate::implementations::{enum_from_str, route_attribute, str_from_enum};
 use crate::misc::english_numerical;
 
 mod implementations {
     pub mod enum_from_str;
+    pub mod route_attribute;
     pub mod str_from_enum;
 }
 
@@ -41,4 +42,47 @@ pub fn string_from_enum_derive(input: TokenStream) -> TokenStream {
 #[proc_macro_derive(EnumFromStr, attributes(english_number_prefix_to_numerical))]
 pub fn string_enum_derive(input: TokenStream) -> TokenStream {
     enum_from_str::impl_enum_from_str(syn::parse_macro_input!(input))
-}
\ No newline at end of file
+}
+
+/// Registers the function as the handler of `GET` requests to the path, e.g.
+/// `#[get("/users/{id}")]`. See `RouteHandlerContainer::insert_annotated_routes`.
+#[proc_macro_attribute]
+pub fn get(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("GET", args, input)
+}
+
+/// Registers the function as the handler of `POST` requests to the path.
+#[proc_macro_attribute]
+pub fn post(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("POST", args, input)
+}
+
+/// Registers the function as the handler of `PUT` requests to the path.
+#[proc_macro_attribute]
+pub fn put(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("PUT", args, input)
+}
+
+/// Registers the function as the handler of `DELETE` requests to the path.
+#[proc_macro_attribute]
+pub fn delete(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("DELETE", args, input)
+}
+
+/// Registers the function as the handler of `PATCH` requests to the path.
+#[proc_macro_attribute]
+pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("PATCH", args, input)
+}
+
+/// Registers the function as the handler of `HEAD` requests to the path.
+#[proc_macro_attribute]
+pub fn head(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("HEAD", args, input)
+}
+
+/// Registers the function as the handler of `OPTIONS` requests to the path.
+#[proc_macro_attribute]
+pub fn options(args: TokenStream, input: TokenStream) -> TokenStream {
+    route_attribute::impl_route_attribute("OPTIONS", args, input)
+}
@@ -0,0 +1,261 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+use di_ioc_lib::di::ioc_container::IocContainer;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::util::parsers::http_date_parser::format_common_log_date;
+use crate::web::util::parsers::json_parser::escape_json_string;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The headers redacted by default, since they carry credentials.
+const DEFAULT_REDACTED_HEADERS: [&str; 5] = ["Authorization", "Proxy-Authorization", "Cookie", "X-CSRF-Token", "X-Api-Key"];
+
+/// The format of the access log lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// `host ident user [time] "request" status bytes`
+    Common,
+    /// The Common Log Format followed by `"referer" "user-agent"`.
+    Combined,
+    /// A JSON object per line, with all fields including the duration, the request ID and the
+    /// request headers.
+    Json
+}
+
+/// `RequestId` identifies a request across logs. It is taken from the `X-Request-Id` header of the
+/// request if it is sane, e.g. when set by a load balancer, and generated otherwise. It is attached
+/// to the request and echoed in the `X-Request-Id` response header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+}
+
+/// When the request arrived, attached to the request by `AccessLog`.
+struct RequestStart {
+    instant: Instant,
+    time: SystemTime
+}
+
+/// `AccessLog` is middleware writing a line per request to a file or writer. Add it as the first
+/// global middleware, so it sees the final response of every request. The duration covers the
+/// middleware and the handler, not writing the response.
+///
+/// Properties:
+///
+/// * `format`: The format of the lines.
+/// * `writer`: Where the lines are written.
+/// * `redacted_headers`: Headers whose values are replaced in JSON lines.
+pub struct AccessLog {
+    format: AccessLogFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+    redacted_headers: Vec<String>
+}
+
+impl AccessLog {
+    /// `new` creates an access log writing to any writer, e.g. `std::io::stdout()`.
+    ///
+    /// Arguments:
+    ///
+    /// * `format`: The format of the lines.
+    /// * `writer`: Where the lines are written.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the AccessLog struct.
+    pub fn new<W: Write + Send + 'static>(format: AccessLogFormat, writer: W) -> Self {
+        Self {
+            format,
+            writer: Mutex::new(Box::new(writer)),
+            redacted_headers: DEFAULT_REDACTED_HEADERS.iter().map(|h: &&str| h.to_string()).collect()
+        }
+    }
+
+    /// `to_file` creates an access log appending to a file, which is created if needed.
+    pub fn to_file<P: AsRef<Path>>(format: AccessLogFormat, path: P) -> io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(format, file))
+    }
+
+    /// It redacts another header in JSON lines. Authorization, Proxy-Authorization, Cookie,
+    /// X-CSRF-Token and X-Api-Key are redacted by default.
+    pub fn redact_header(mut self, name: &str) -> Self {
+        self.redacted_headers.push(name.to_string());
+        self
+    }
+
+    fn is_redacted(&self, name: &str) -> bool {
+        self.redacted_headers.iter().any(|redacted: &String| redacted.eq_ignore_ascii_case(name))
+    }
+
+    /// It formats the log line of a request.
+    fn format_line(&self, req: &Request, res: &Response, request_id: &str, start: &RequestStart) -> String {
+        let host: String = req.peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let time: String = format_common_log_date(start.time);
+        let line_data = req.request_line_data();
+        let bytes: u64 = res.body_length().unwrap_or(0);
+        let referer: &str = req.get_header("Referer").unwrap_or("-");
+        let user_agent: &str = req.get_header("User-Agent").unwrap_or("-");
+        match self.format {
+            AccessLogFormat::Common | AccessLogFormat::Combined => {
+                let request: String = format!("{} {} {}", line_data.method(), line_data.target(), line_data.protocol());
+                let bytes: String = if bytes == 0 { "-".to_string() } else { bytes.to_string() };
+                let mut line: String = format!("{} - - [{}] \"{}\" {} {}",
+                                               host, time, escape_log_field(&request), res.status(), bytes);
+                if self.format == AccessLogFormat::Combined {
+                    line.push_str(&format!(" \"{}\" \"{}\"", escape_log_field(referer), escape_log_field(user_agent)));
+                }
+                line
+            },
+            AccessLogFormat::Json => {
+                let mut headers: Vec<(&str, &str)> = req.headers().iter().collect();
+                headers.sort();
+                let headers: Vec<String> = headers.iter()
+                    .map(|(name, value)| {
+                        let value: &str = if self.is_redacted(name) { "[REDACTED]" } else { value };
+                        format!("\"{}\":\"{}\"", escape_json_string(name), escape_json_string(value))
+                    })
+                    .collect();
+                format!("{{\"time\":\"{}\",\"remote_addr\":\"{}\",\"request_id\":\"{}\",\"method\":\"{}\",\
+                         \"path\":\"{}\",\"protocol\":\"{}\",\"status\":{},\"bytes\":{},\"duration_ms\":{:.3},\
+                         \"referer\":\"{}\",\"user_agent\":\"{}\",\"headers\":{{{}}}}}",
+                        time, host, escape_json_string(request_id), escape_json_string(line_data.method()),
+                        escape_json_string(line_data.target()), escape_json_string(line_data.protocol()),
+                        res.status(), bytes, start.instant.elapsed().as_secs_f64() * 1000.0,
+                        escape_json_string(referer), escape_json_string(user_agent), headers.join(","))
+            }
+        }
+    }
+}
+
+/// It escapes a field quoted in a Common Log Format line, like Apache does, so a client can't
+/// forge log lines.
+fn escape_log_field(field: &str) -> String {
+    let mut escaped: String = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+/// It takes the request ID sent by the client, if it is short and printable, or creates a new one.
+fn request_id_of(req: &Request) -> RequestId {
+    let sent: Option<&str> = req.get_header("X-Request-Id")
+        .map(|id: &str| id.trim())
+        .filter(|id: &&str| !id.is_empty() && id.len() <= 128
+            && id.chars().all(|c: char| c.is_ascii_graphic()));
+    match sent {
+        Some(id) => RequestId(id.to_string()),
+        None => {
+            let mut bytes: [u8; 16] = [0; 16];
+            OsRng.fill_bytes(&mut bytes);
+            RequestId(bytes.iter().map(|b: &u8| format!("{:02x}", b)).collect())
+        }
+    }
+}
+
+impl Middleware for AccessLog {
+    fn before(&self, req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        let request_id: RequestId = request_id_of(req);
+        req.extensions_mut().insert(request_id);
+        req.extensions_mut().insert(RequestStart { instant: Instant::now(), time: SystemTime::now() });
+        None
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        let (request_id, start) = match (req.request_id(), req.extension::<RequestStart>()) {
+            (Some(request_id), Some(start)) => (request_id, start),
+            _ => return
+        };
+        res.add_header("X-Request-Id", request_id.value().to_string());
+        let line: String = self.format_line(req, res, request_id.value(), start);
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+            log::error!("Failed to write access log: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::access_log_middleware::{AccessLog, AccessLogFormat};
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log_line(format: AccessLogFormat) -> String {
+        let buffer: SharedBuffer = SharedBuffer::default();
+        let access_log: AccessLog = AccessLog::new(format, buffer.clone()).redact_header("X-Secret");
+        let container: IocContainer = IocContainer::default();
+        let mut req: Request = Request::new_without_stream(
+            b"GET /items?page=2 HTTP/1.1\nUser-Agent: curl/8.0 \"quoted\"\nReferer: https://example.com/\n\
+              Authorization: Bearer abc\nX-Secret: s3cr3t\nX-Request-Id: req-1", b"").unwrap();
+        assert!(access_log.before(&mut req, &container).is_none());
+        let mut res: Response = Response::ok();
+        res.set_body("hello".to_string());
+        access_log.after(&req, &mut res, &container);
+        assert_eq!(res.headers().get("X-Request-Id").unwrap(), "req-1");
+        let line: String = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        line
+    }
+
+    #[test]
+    fn writes_combined_lines() {
+        let line: String = log_line(AccessLogFormat::Combined);
+        assert!(line.starts_with("- - - ["));
+        assert!(line.ends_with("] \"GET /items?page=2 HTTP/1.1\" 200 5 \"https://example.com/\" \"curl/8.0 \\\"quoted\\\"\"\n"));
+    }
+
+    #[test]
+    fn writes_json_lines_with_redacted_headers() {
+        let line: String = log_line(AccessLogFormat::Json);
+        assert!(line.contains("\"request_id\":\"req-1\",\"method\":\"GET\",\"path\":\"/items?page=2\""));
+        assert!(line.contains("\"status\":200,\"bytes\":5,"));
+        assert!(line.contains("\"Authorization\":\"[REDACTED]\""));
+        assert!(line.contains("\"X-Secret\":\"[REDACTED]\""));
+        assert!(line.contains("\"User-Agent\":\"curl/8.0 \\\"quoted\\\"\""));
+        assert!(!line.contains("abc") && !line.contains("s3cr3t"));
+    }
+}
@@ -0,0 +1,252 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use di_ioc_lib::di::providable_trait::Providable;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `BasicVerifier` checks the username and password of `Authorization: Basic` requests. Install
+/// it in the `IocContainer` with `install_reference_provider`, so `BasicAuth` can resolve it.
+pub trait BasicVerifier: Providable + Send + Sync {
+    /// The authenticated user, attached to the request as an extension.
+    type Principal: Any + Send + Sync;
+
+    /// It checks the credentials.
+    ///
+    /// Returns:
+    ///
+    /// The principal, or `None` if the credentials are wrong.
+    fn verify(&self, username: &str, password: &str) -> Option<Self::Principal>;
+}
+
+/// `BearerVerifier` checks the token of `Authorization: Bearer` requests. Install it in the
+/// `IocContainer` with `install_reference_provider`, so `BearerAuth` can resolve it.
+pub trait BearerVerifier: Providable + Send + Sync {
+    /// The authenticated user, attached to the request as an extension.
+    type Principal: Any + Send + Sync;
+
+    /// It checks the token.
+    ///
+    /// Returns:
+    ///
+    /// The principal, or `None` if the token is invalid or expired.
+    fn verify(&self, token: &str) -> Option<Self::Principal>;
+}
+
+/// It splits the `Authorization` header of a request into its scheme and credentials, if the
+/// scheme matches. Schemes are case-insensitive.
+///
+/// Arguments:
+///
+/// * `req`: The request.
+/// * `scheme`: The expected scheme, e.g. "Basic".
+///
+/// Returns:
+///
+/// The credentials, or `None` if the header is missing or uses another scheme.
+pub fn authorization_credentials<'a>(req: &'a Request, scheme: &str) -> Option<&'a str> {
+    let header: &str = req.get_header("Authorization")?.trim();
+    let (found_scheme, credentials) = header.split_once(' ')?;
+    if found_scheme.eq_ignore_ascii_case(scheme) {
+        Some(credentials.trim())
+    } else {
+        None
+    }
+}
+
+/// It decodes the credentials of `Authorization: Basic` into the username and password.
+///
+/// Arguments:
+///
+/// * `credentials`: The base64 encoded "username:password".
+///
+/// Returns:
+///
+/// The username and password, or `None` if the credentials are malformed.
+pub fn decode_basic_credentials(credentials: &str) -> Option<(String, String)> {
+    let decoded: Vec<u8> = STANDARD.decode(credentials).ok()?;
+    let decoded: String = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+/// It creates a `401 Unauthorized` response asking for credentials.
+fn unauthorized(req: &Request, challenge: String) -> Response {
+    let mut res: Response = fallback_handlers::negotiated_error_response(Some(req), StatusCode::UNAUTHORIZED);
+    res.add_header("WWW-Authenticate", challenge);
+    res
+}
+
+/// It creates the response for a verifier missing in the container, which is a setup mistake.
+fn verifier_missing(req: &Request, verifier: &str) -> Response {
+    log::error!("No {} installed in the IocContainer", verifier);
+    fallback_handlers::negotiated_error_response(Some(req), StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// `BasicAuth` is middleware authenticating requests with `Authorization: Basic`. The credentials
+/// are checked by the `BasicVerifier` `V` from the `IocContainer`, and the principal it returns is
+/// attached to the request, where handlers read it with `req.extension::<V::Principal>()`.
+/// Requests without valid credentials get a `401 Unauthorized` with a `WWW-Authenticate` header.
+///
+/// Properties:
+///
+/// * `realm`: The realm sent in the `WWW-Authenticate` header.
+pub struct BasicAuth<V: BasicVerifier> {
+    realm: String,
+    verifier: PhantomData<fn() -> V>
+}
+
+impl <V: BasicVerifier> BasicAuth<V> {
+    pub fn new(realm: &str) -> Self {
+        Self { realm: realm.to_string(), verifier: PhantomData }
+    }
+}
+
+impl <V: BasicVerifier> Middleware for BasicAuth<V> {
+    fn before(&self, req: &mut Request, container: &IocContainer) -> Option<Response> {
+        let verifier: &V = match container.get_ref::<V>() {
+            Ok(verifier) => verifier,
+            Err(_) => return Some(verifier_missing(req, std::any::type_name::<V>()))
+        };
+        let principal: Option<V::Principal> = authorization_credentials(req, "Basic")
+            .and_then(decode_basic_credentials)
+            .and_then(|(username, password)| verifier.verify(&username, &password));
+        match principal {
+            Some(principal) => {
+                req.extensions_mut().insert(principal);
+                None
+            },
+            None => Some(unauthorized(req, format!("Basic realm=\"{}\", charset=\"UTF-8\"", self.realm)))
+        }
+    }
+}
+
+/// `BearerAuth` is middleware authenticating requests with `Authorization: Bearer`. The token is
+/// checked by the `BearerVerifier` `V` from the `IocContainer`, and the principal it returns is
+/// attached to the request, where handlers read it with `req.extension::<V::Principal>()`.
+/// Requests without a valid token get a `401 Unauthorized` with a `WWW-Authenticate` header, which
+/// reports `invalid_token` if a token was sent.
+///
+/// Properties:
+///
+/// * `realm`: The realm sent in the `WWW-Authenticate` header.
+pub struct BearerAuth<V: BearerVerifier> {
+    realm: String,
+    verifier: PhantomData<fn() -> V>
+}
+
+impl <V: BearerVerifier> BearerAuth<V> {
+    pub fn new(realm: &str) -> Self {
+        Self { realm: realm.to_string(), verifier: PhantomData }
+    }
+}
+
+impl <V: BearerVerifier> Middleware for BearerAuth<V> {
+    fn before(&self, req: &mut Request, container: &IocContainer) -> Option<Response> {
+        let verifier: &V = match container.get_ref::<V>() {
+            Ok(verifier) => verifier,
+            Err(_) => return Some(verifier_missing(req, std::any::type_name::<V>()))
+        };
+        let token: Option<String> = authorization_credentials(req, "Bearer")
+            .map(|token: &str| token.to_string());
+        match token.as_deref().and_then(|token: &str| verifier.verify(token)) {
+            Some(principal) => {
+                req.extensions_mut().insert(principal);
+                None
+            },
+            None if token.is_some() => Some(unauthorized(req, format!(
+                "Bearer realm=\"{}\", error=\"invalid_token\"", self.realm))),
+            None => Some(unauthorized(req, format!("Bearer realm=\"{}\"", self.realm)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use di_ioc_lib::di::providable_trait::Providable;
+    use crate::web::middleware::auth_middleware::{BasicAuth, BasicVerifier, BearerAuth, BearerVerifier};
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+
+    struct Users;
+    impl Providable for Users { }
+
+    #[derive(Debug, PartialEq)]
+    struct User(String);
+
+    impl BasicVerifier for Users {
+        type Principal = User;
+        fn verify(&self, username: &str, password: &str) -> Option<User> {
+            (username == "admin" && password == "secret").then(|| User(username.to_string()))
+        }
+    }
+
+    impl BearerVerifier for Users {
+        type Principal = User;
+        fn verify(&self, token: &str) -> Option<User> {
+            (token == "token123").then(|| User("api".to_string()))
+        }
+    }
+
+    fn container() -> IocContainer {
+        let mut container: IocContainer = IocContainer::default();
+        container.install_reference_provider(Arc::new(Users));
+        container
+    }
+
+    #[test]
+    fn basic_auth() {
+        let container: IocContainer = container();
+        let auth: BasicAuth<Users> = BasicAuth::new("admin");
+        // admin:secret
+        let mut req: Request = Request::new_without_stream(
+            b"GET /admin HTTP/1.1\nAuthorization: basic YWRtaW46c2VjcmV0", b"").unwrap();
+        assert!(auth.before(&mut req, &container).is_none());
+        assert_eq!(req.extension::<User>(), Some(&User("admin".to_string())));
+
+        // admin:wrong
+        let mut req: Request = Request::new_without_stream(
+            b"GET /admin HTTP/1.1\nAuthorization: Basic YWRtaW46d3Jvbmc=", b"").unwrap();
+        let res: Response = auth.before(&mut req, &container).unwrap();
+        assert_eq!(res.status(), 401);
+        assert_eq!(res.headers().get("WWW-Authenticate").unwrap(), "Basic realm=\"admin\", charset=\"UTF-8\"");
+    }
+
+    #[test]
+    fn bearer_auth() {
+        let container: IocContainer = container();
+        let auth: BearerAuth<Users> = BearerAuth::new("api");
+        let mut req: Request = Request::new_without_stream(
+            b"GET /api HTTP/1.1\nAuthorization: Bearer token123", b"").unwrap();
+        assert!(auth.before(&mut req, &container).is_none());
+        assert_eq!(req.extension::<User>(), Some(&User("api".to_string())));
+
+        let mut req: Request = Request::new_without_stream(b"GET /api HTTP/1.1\nHost: a", b"").unwrap();
+        let res: Response = auth.before(&mut req, &container).unwrap();
+        assert_eq!(res.headers().get("WWW-Authenticate").unwrap(), "Bearer realm=\"api\"");
+
+        let mut req: Request = Request::new_without_stream(
+            b"GET /api HTTP/1.1\nAuthorization: Bearer expired", b"").unwrap();
+        let res: Response = auth.before(&mut req, &container).unwrap();
+        assert_eq!(res.headers().get("WWW-Authenticate").unwrap(), "Bearer realm=\"api\", error=\"invalid_token\"");
+    }
+}
@@ -0,0 +1,258 @@
+use std::io;
+use std::io::Write;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use flate2::Compression as Level;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::etag::ETag;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::util::enums::mime_types::MimeTypes;
+use crate::web::util::parsers::quality_value_parser;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The codings offered to clients, in the order the server prefers them.
+#[cfg(feature = "brotli")]
+const OFFERED_CODINGS: [&str; 3] = ["br", "gzip", "deflate"];
+#[cfg(not(feature = "brotli"))]
+const OFFERED_CODINGS: [&str; 2] = ["gzip", "deflate"];
+
+/// The types compressed by default. Images, audio, video and archives are already compressed.
+const DEFAULT_COMPRESSIBLE: [MimeTypes; 14] = [
+    MimeTypes::HTML, MimeTypes::XHTML, MimeTypes::CSS, MimeTypes::JS, MimeTypes::JSON,
+    MimeTypes::JSONLD, MimeTypes::XML, MimeTypes::SVG, MimeTypes::TXT, MimeTypes::CSV,
+    MimeTypes::ICS, MimeTypes::TTF, MimeTypes::OTF, MimeTypes::EOT
+];
+
+/// `Compression` is middleware compressing response bodies with gzip or deflate, and brotli with
+/// the `brotli` cargo feature. The coding is negotiated from the `Accept-Encoding` header,
+/// including q-values, and only bodies of at least `threshold` bytes with a compressible
+/// `Content-Type` are compressed. Compressible responses get `Vary: Accept-Encoding`, compressed
+/// ones `Content-Encoding`. Streamed bodies of known length up to `buffer_limit`, like files from
+/// `set_body_to_file` and static files, are read into memory to be compressed, and lose
+/// `Accept-Ranges`, since ranges of the compressed bytes aren't served. Other streamed bodies are
+/// sent as they are.
+///
+/// Add it as one of the first global middleware, so it compresses the final body.
+///
+/// Properties:
+///
+/// * `threshold`: The minimum body size in bytes worth compressing.
+/// * `compressible`: The media types that are compressed, e.g. "text/css".
+/// * `buffer_limit`: The largest streamed body in bytes that is read into memory to be compressed.
+pub struct Compression {
+    threshold: usize,
+    compressible: Vec<String>,
+    buffer_limit: u64
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compression {
+    /// `new` creates the middleware with a threshold of 1024 bytes, compressing text, JSON, XML,
+    /// SVG and font types, and files up to 1 MiB.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Compression struct.
+    pub fn new() -> Self {
+        Self {
+            threshold: 1024,
+            compressible: DEFAULT_COMPRESSIBLE.iter().map(|mime: &MimeTypes| mime.to_string()).collect(),
+            buffer_limit: 1024 * 1024
+        }
+    }
+
+    /// It sets the minimum body size in bytes worth compressing. Smaller bodies are sent as they are.
+    pub fn threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// It sets the largest streamed body in bytes that is read into memory to be compressed.
+    pub fn buffer_limit(mut self, buffer_limit: u64) -> Self {
+        self.buffer_limit = buffer_limit;
+        self
+    }
+
+    /// It adds a type to the compressed types.
+    pub fn allow(mut self, mime: MimeTypes) -> Self {
+        self.compressible.push(mime.to_string());
+        self
+    }
+
+    /// It checks if a `Content-Type` value, which may have parameters like a charset, is
+    /// compressible.
+    fn is_compressible(&self, content_type: &str) -> bool {
+        let media_type: &str = content_type.split(';').next().unwrap_or("").trim();
+        self.compressible.iter().any(|mime: &String| mime.eq_ignore_ascii_case(media_type))
+    }
+}
+
+/// It compresses a body with a coding offered in `OFFERED_CODINGS`.
+fn compress(coding: &str, body: &[u8]) -> io::Result<Vec<u8>> {
+    match coding {
+        "gzip" => {
+            let mut encoder: GzEncoder<Vec<u8>> = GzEncoder::new(Vec::new(), Level::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        },
+        "deflate" => {
+            let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Level::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        },
+        #[cfg(feature = "brotli")]
+        "br" => {
+            let mut compressed: Vec<u8> = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+                encoder.write_all(body)?;
+            }
+            Ok(compressed)
+        },
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown coding: {}", coding)))
+    }
+}
+
+impl Middleware for Compression {
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        let compressible: bool = res.headers().get("Content-Type")
+            .is_some_and(|content_type: &str| self.is_compressible(content_type));
+        let length: Option<u64> = res.body_length();
+        let bufferable: bool = !res.is_streaming() || length.is_some_and(|length: u64| length <= self.buffer_limit);
+        if !compressible || !bufferable || res.headers().contains_key("Content-Encoding")
+            || res.status().is_informational()
+            || matches!(res.status().as_u16(), 204 | 206 | 304) {
+            return;
+        }
+        res.add_vary("Accept-Encoding");
+        if length.unwrap_or(0) < self.threshold as u64 {
+            return;
+        }
+        let coding: &str = match req.get_header("Accept-Encoding")
+            .and_then(|accept: &str| quality_value_parser::negotiate(accept, &OFFERED_CODINGS)) {
+            Some(coding) => coding,
+            None => return
+        };
+        if let Err(e) = res.buffer_body(self.buffer_limit) {
+            log::error!("Failed to read response body to compress it: {}", e);
+            return;
+        }
+        match compress(coding, res.body()) {
+            Ok(compressed) if compressed.len() < res.body().len() => {
+                res.set_body_u8(compressed);
+                res.add_header("Content-Encoding", coding.to_string());
+                res.remove_header("Accept-Ranges");
+                // The compressed bytes differ, so a strong tag of the original no longer holds.
+                if let Some(etag) = res.etag().filter(|etag: &ETag| !etag.is_weak()) {
+                    res.set_etag(etag.into_weak());
+                }
+            },
+            Ok(_) => { },
+            Err(e) => log::error!("Failed to compress response: {}", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use crate::web::middleware::compression_middleware::Compression;
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::util::enums::mime_types::MimeTypes;
+
+    fn compressed(accept_encoding: &str, mime: MimeTypes, body: &str) -> Response {
+        let head: String = format!("GET / HTTP/1.1\nAccept-Encoding: {}", accept_encoding);
+        let req: Request = Request::new_without_stream(head.as_bytes(), b"").unwrap();
+        let mut res: Response = Response::ok();
+        res.content_type(mime);
+        res.set_body(body.to_string());
+        Compression::new().threshold(100).after(&req, &mut res, &IocContainer::default());
+        res
+    }
+
+    #[test]
+    fn negotiates_coding() {
+        let body: String = "body { color: red; }\n".repeat(20);
+        let res: Response = compressed("deflate, gzip;q=0.5", MimeTypes::CSS, &body);
+        assert_eq!(res.headers().get("Content-Encoding").unwrap(), "deflate");
+        assert_eq!(res.headers().get("Vary").unwrap(), "Accept-Encoding");
+        let mut decompressed: String = String::new();
+        ZlibDecoder::new(res.body()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+
+        let res: Response = compressed("gzip, deflate;q=0", MimeTypes::JSON, &body);
+        let mut decompressed: String = String::new();
+        GzDecoder::new(res.body()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+
+        let res: Response = compressed("gzip;q=0, identity", MimeTypes::CSS, &body);
+        assert!(!res.headers().contains_key("Content-Encoding"));
+        assert_eq!(res.body(), body.as_bytes());
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn prefers_brotli() {
+        let body: String = "<p>Hello</p>\n".repeat(20);
+        let res: Response = compressed("gzip, deflate, br", MimeTypes::HTML, &body);
+        assert_eq!(res.headers().get("Content-Encoding").unwrap(), "br");
+        let mut decompressed: String = String::new();
+        brotli::Decompressor::new(res.body(), 4096).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+    }
+
+    #[test]
+    fn skips_small_and_incompressible_bodies() {
+        let res: Response = compressed("gzip", MimeTypes::CSS, "body { }");
+        assert!(!res.headers().contains_key("Content-Encoding"));
+        assert_eq!(res.headers().get("Vary").unwrap(), "Accept-Encoding");
+        let res: Response = compressed("gzip", MimeTypes::PNG, &"x".repeat(1000));
+        assert!(!res.headers().contains_key("Content-Encoding"));
+        assert!(!res.headers().contains_key("Vary"));
+    }
+
+    #[test]
+    fn compresses_files() {
+        let path = std::env::temp_dir().join(format!("compressed-{}.css", std::process::id()));
+        let body: String = "body { color: red; }\n".repeat(100);
+        std::fs::write(&path, &body).unwrap();
+        let req: Request = Request::new_without_stream(b"GET /app.css HTTP/1.1\nAccept-Encoding: gzip", b"").unwrap();
+        let mut res: Response = Response::ok();
+        res.set_body_to_path(&path).unwrap();
+        Compression::new().after(&req, &mut res, &IocContainer::default());
+        assert_eq!(res.headers().get("Content-Encoding").unwrap(), "gzip");
+        assert!(!res.headers().contains_key("Accept-Ranges"));
+        assert!(res.etag().unwrap().is_weak());
+        let mut decompressed: String = String::new();
+        GzDecoder::new(res.body()).read_to_string(&mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+
+        let mut res: Response = Response::ok();
+        res.set_body_to_path(&path).unwrap();
+        Compression::new().buffer_limit(100).after(&req, &mut res, &IocContainer::default());
+        assert!(res.is_streaming());
+        assert!(!res.headers().contains_key("Content-Encoding"));
+        assert_eq!(res.headers().get("Accept-Ranges").unwrap(), "bytes");
+        std::fs::remove_file(path).unwrap();
+    }
+}
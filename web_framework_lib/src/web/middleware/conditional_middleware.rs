@@ -0,0 +1,192 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use di_ioc_lib::di::ioc_container::IocContainer;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::etag::ETag;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::util::parsers::http_date_parser::parse_http_date;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The outcome of evaluating the conditional headers of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+    /// The request can be processed normally.
+    Passed,
+    /// The client's copy is current, answer with `304 Not Modified`.
+    NotModified,
+    /// A precondition failed, answer with `412 Precondition Failed`.
+    Failed
+}
+
+/// Seconds since the epoch, the resolution of HTTP dates.
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// It evaluates `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since`
+/// against the current version of a resource, in the order given by RFC 9110. Handlers of unsafe
+/// methods call it before changing anything, e.g. so a PUT with an outdated `If-Match` fails
+/// instead of overwriting someone else's change.
+///
+/// Arguments:
+///
+/// * `req`: The request.
+/// * `etag`: The current entity tag of the resource, if it has one.
+/// * `last_modified`: When the resource was last changed, if known.
+///
+/// Returns:
+///
+/// If the request should be processed, answered with 304, or answered with 412.
+pub fn evaluate_preconditions(req: &Request, etag: Option<&ETag>, last_modified: Option<SystemTime>) -> Precondition {
+    let method: &str = req.request_line_data().method();
+    let is_get_or_head: bool = method == "GET" || method == "HEAD";
+    if let Some(if_match) = req.get_header("If-Match") {
+        let matches: bool = match ETag::parse_list(if_match) {
+            None => true,
+            Some(tags) => etag.is_some_and(|etag: &ETag| tags.iter().any(|tag: &ETag| tag.strong_eq(etag)))
+        };
+        if !matches {
+            return Precondition::Failed;
+        }
+    } else if let (Some(since), Some(last_modified)) = (
+        req.get_header("If-Unmodified-Since").and_then(parse_http_date), last_modified) {
+        if unix_secs(last_modified) > unix_secs(since) {
+            return Precondition::Failed;
+        }
+    }
+    if let Some(if_none_match) = req.get_header("If-None-Match") {
+        let matches: bool = match ETag::parse_list(if_none_match) {
+            None => true,
+            Some(tags) => etag.is_some_and(|etag: &ETag| tags.iter().any(|tag: &ETag| tag.weak_eq(etag)))
+        };
+        if matches {
+            return if is_get_or_head { Precondition::NotModified } else { Precondition::Failed };
+        }
+    } else if let (true, Some(since), Some(last_modified)) = (
+        is_get_or_head, req.get_header("If-Modified-Since").and_then(parse_http_date), last_modified) {
+        if unix_secs(last_modified) <= unix_secs(since) {
+            return Precondition::NotModified;
+        }
+    }
+    Precondition::Passed
+}
+
+/// It evaluates the preconditions like `evaluate_preconditions`, and creates the response to
+/// answer with if the request shouldn't be processed.
+///
+/// Returns:
+///
+/// A `304 Not Modified` or `412 Precondition Failed` response, or `None` to process the request.
+pub fn precondition_response(req: &Request, etag: Option<&ETag>, last_modified: Option<SystemTime>) -> Option<Response> {
+    match evaluate_preconditions(req, etag, last_modified) {
+        Precondition::Passed => None,
+        Precondition::NotModified => {
+            let mut res: Response = Response::new(StatusCode::NOT_MODIFIED);
+            if let Some(etag) = etag {
+                res.set_etag(etag.clone());
+            }
+            Some(res)
+        },
+        Precondition::Failed => Some(fallback_handlers::negotiated_error_response(
+            Some(req), StatusCode::PRECONDITION_FAILED))
+    }
+}
+
+/// `ConditionalRequests` is middleware answering GET and HEAD requests with `304 Not Modified` when
+/// the client's cached copy is still current, or `412 Precondition Failed`, based on the `ETag`
+/// and `Last-Modified` headers of the successful response. Files get both automatically, dynamic
+/// responses with `res.set_etag` and `res.set_last_modified`.
+///
+/// Add it after `Compression`, so a 304 is not compressed. Unsafe methods are not evaluated, since
+/// the handler already ran; their handlers call `precondition_response` first.
+#[derive(Default)]
+pub struct ConditionalRequests;
+
+impl ConditionalRequests {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Middleware for ConditionalRequests {
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        let method: &str = req.request_line_data().method();
+        if !(method == "GET" || method == "HEAD") || !res.status().is_success() {
+            return;
+        }
+        match evaluate_preconditions(req, res.etag().as_ref(), res.last_modified()) {
+            Precondition::Passed => { },
+            Precondition::NotModified => {
+                res.set_status(StatusCode::NOT_MODIFIED);
+                res.set_body_u8(Vec::new());
+                res.remove_header("Content-Range");
+            },
+            Precondition::Failed => {
+                *res = fallback_handlers::negotiated_error_response(Some(req), StatusCode::PRECONDITION_FAILED);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::conditional_middleware::{ConditionalRequests, evaluate_preconditions, Precondition};
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::etag::ETag;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+
+    fn request(method: &str, header: &str) -> Request {
+        Request::new_without_stream(format!("{} /doc HTTP/1.1\n{}", method, header).as_bytes(), b"").unwrap()
+    }
+
+    #[test]
+    fn evaluates_preconditions() {
+        let etag: ETag = ETag::strong("v2");
+        let modified: SystemTime = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let evaluate = |method: &str, header: &str| evaluate_preconditions(&request(method, header), Some(&etag), Some(modified));
+
+        assert_eq!(evaluate("GET", "If-None-Match: \"v1\", W/\"v2\""), Precondition::NotModified);
+        assert_eq!(evaluate("GET", "If-None-Match: \"v1\""), Precondition::Passed);
+        assert_eq!(evaluate("PUT", "If-None-Match: *"), Precondition::Failed);
+        assert_eq!(evaluate("GET", "If-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT"), Precondition::NotModified);
+        assert_eq!(evaluate("GET", "If-Modified-Since: Sun, 06 Nov 1994 08:49:36 GMT"), Precondition::Passed);
+        assert_eq!(evaluate("GET", "If-Modified-Since: yesterday"), Precondition::Passed);
+        assert_eq!(evaluate("PUT", "If-Match: \"v2\""), Precondition::Passed);
+        assert_eq!(evaluate("PUT", "If-Match: W/\"v2\""), Precondition::Failed);
+        assert_eq!(evaluate("PUT", "If-Match: \"v1\""), Precondition::Failed);
+        assert_eq!(evaluate("PUT", "If-Unmodified-Since: Sun, 06 Nov 1994 08:49:36 GMT"), Precondition::Failed);
+        assert_eq!(evaluate_preconditions(&request("PUT", "If-Match: *"), None, None), Precondition::Passed);
+    }
+
+    #[test]
+    fn answers_with_not_modified() {
+        let container: IocContainer = IocContainer::default();
+        let mut res: Response = Response::ok();
+        res.set_etag(ETag::strong("v2"));
+        res.set_body("content".to_string());
+        ConditionalRequests::new().after(&request("GET", "If-None-Match: \"v2\""), &mut res, &container);
+        assert_eq!(res.status(), 304);
+        assert!(res.body().is_empty());
+        assert_eq!(res.headers().get("ETag").unwrap(), "\"v2\"");
+
+        let mut res: Response = Response::ok();
+        res.set_etag(ETag::strong("v2"));
+        ConditionalRequests::new().after(&request("GET", "If-Match: \"v1\""), &mut res, &container);
+        assert_eq!(res.status(), 412);
+    }
+}
@@ -0,0 +1,328 @@
+use std::str::FromStr;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::util::enums::http_method_enum::HttpMethod;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// An origin, or a set of origins, allowed to make cross-origin requests.
+pub enum AllowedOrigin {
+    /// Any origin. Answered with `*`, or with the request's origin when credentials are allowed.
+    Any,
+    /// A single origin, e.g. "https://example.com".
+    Exact(String),
+    /// An origin pattern where `*` matches anything, e.g. "https://*.example.com".
+    Wildcard(String),
+    /// A function deciding whether an origin is allowed.
+    Predicate(Box<dyn Fn(&str) -> bool + Send + Sync>)
+}
+
+impl AllowedOrigin {
+    /// It checks whether the origin is allowed by this entry.
+    ///
+    /// Arguments:
+    ///
+    /// * `origin`: The value of the request's `Origin` header.
+    ///
+    /// Returns:
+    ///
+    /// A boolean value.
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            AllowedOrigin::Any => true,
+            AllowedOrigin::Exact(allowed) => allowed.eq_ignore_ascii_case(origin),
+            AllowedOrigin::Wildcard(pattern) => wildcard_match(pattern, origin),
+            AllowedOrigin::Predicate(predicate) => predicate(origin)
+        }
+    }
+}
+
+/// `Cors` is middleware implementing Cross-Origin Resource Sharing. It answers preflight
+/// `OPTIONS` requests itself, and adds the `Access-Control-*` headers to the responses of actual
+/// cross-origin requests. Requests without an `Origin` header pass through untouched, apart from
+/// `Vary: Origin`.
+///
+/// Add it with `RouteHandlerContainer::add_middleware`: preflight requests are `OPTIONS` requests,
+/// which match no route, so a `Cors` added with `add_route_middleware` never sees them.
+///
+/// Nothing is allowed by default, e.g.
+/// ```
+/// use web_framework_lib::web::middleware::cors_middleware::{AllowedOrigin, Cors};
+/// use web_framework_lib::web::util::enums::http_method_enum::HttpMethod;
+/// let cors: Cors = Cors::new()
+///     .allow_origin(AllowedOrigin::Wildcard("https://*.example.com".to_string()))
+///     .allow_methods(&[HttpMethod::GET, HttpMethod::POST])
+///     .allow_headers(&["Content-Type"])
+///     .max_age(600);
+/// ```
+///
+/// Properties:
+///
+/// * `allowed_origins`: The origins allowed to make requests.
+/// * `allowed_methods`: The methods allowed in preflight requests.
+/// * `allowed_headers`: The request headers allowed in preflight requests.
+/// * `allow_any_header`: Whether any requested header is allowed.
+/// * `exposed_headers`: Response headers the browser may expose to scripts.
+/// * `allow_credentials`: Whether cookies and authorization may be sent.
+/// * `max_age`: How many seconds browsers may cache a preflight response.
+#[derive(Default)]
+pub struct Cors {
+    allowed_origins: Vec<AllowedOrigin>,
+    allowed_methods: Vec<HttpMethod>,
+    allowed_headers: Vec<String>,
+    allow_any_header: bool,
+    exposed_headers: Vec<String>,
+    allow_credentials: bool,
+    max_age: Option<u32>
+}
+
+impl Cors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow_origin(mut self, origin: AllowedOrigin) -> Self {
+        self.allowed_origins.push(origin);
+        self
+    }
+    pub fn allow_methods(mut self, methods: &[HttpMethod]) -> Self {
+        self.allowed_methods.extend_from_slice(methods);
+        self
+    }
+    pub fn allow_headers(mut self, headers: &[&str]) -> Self {
+        self.allowed_headers.extend(headers.iter().map(|header: &&str| header.to_string()));
+        self
+    }
+    pub fn allow_any_header(mut self) -> Self {
+        self.allow_any_header = true;
+        self
+    }
+    pub fn expose_headers(mut self, headers: &[&str]) -> Self {
+        self.exposed_headers.extend(headers.iter().map(|header: &&str| header.to_string()));
+        self
+    }
+    pub fn allow_credentials(mut self, allow_credentials: bool) -> Self {
+        self.allow_credentials = allow_credentials;
+        self
+    }
+    pub fn max_age(mut self, seconds: u32) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed: &AllowedOrigin| allowed.matches(origin))
+    }
+
+    /// Whether every origin is answered with `*`. The wildcard can't be used together with
+    /// credentials, so the origin is echoed then, and the response depends on it.
+    fn answers_any_origin(&self) -> bool {
+        !self.allow_credentials && self.allowed_origins.iter()
+            .any(|allowed: &AllowedOrigin| matches!(allowed, AllowedOrigin::Any))
+    }
+
+    /// The value of `Access-Control-Allow-Origin` for an allowed origin.
+    fn allow_origin_value(&self, origin: &str) -> String {
+        if self.answers_any_origin() { "*".to_string() } else { origin.to_string() }
+    }
+
+    /// It answers a preflight request, or rejects it with a 403 if the origin, the method or one
+    /// of the headers isn't allowed.
+    fn preflight(&self, origin: &str, requested_method: &str, requested_headers: Option<&str>)
+        -> Response {
+        let method_allowed: bool = HttpMethod::from_str(requested_method)
+            .is_ok_and(|method: HttpMethod| self.allowed_methods.contains(&method));
+        let requested_headers: Vec<&str> = requested_headers
+            .map(|headers: &str| headers.split(',')
+                .map(|header: &str| header.trim())
+                .filter(|header: &&str| !header.is_empty())
+                .collect())
+            .unwrap_or_default();
+        let headers_allowed: bool = self.allow_any_header || requested_headers.iter()
+            .all(|requested: &&str| self.allowed_headers.iter()
+                .any(|allowed: &String| allowed.eq_ignore_ascii_case(requested)));
+        if !self.is_origin_allowed(origin) || !method_allowed || !headers_allowed {
+            if log::log_enabled!(log::Level::Debug) {
+                log::debug!("Rejected CORS preflight from: {}, method: {}, headers: {:?}",
+                    origin, requested_method, requested_headers);
+            }
+            return Response::new(StatusCode::FORBIDDEN);
+        }
+        let mut res: Response = Response::new(StatusCode::NO_CONTENT);
+        self.add_origin_headers(origin, &mut res);
+        let methods: Vec<String> = self.allowed_methods.iter()
+            .map(|method: &HttpMethod| method.to_string())
+            .collect();
+        res.add_header("Access-Control-Allow-Methods", methods.join(", "));
+        if !requested_headers.is_empty() {
+            let allowed_headers: String = if self.allow_any_header {
+                requested_headers.join(", ")
+            } else {
+                self.allowed_headers.join(", ")
+            };
+            res.add_header("Access-Control-Allow-Headers", allowed_headers);
+        }
+        if let Some(max_age) = self.max_age {
+            res.add_header("Access-Control-Max-Age", max_age.to_string());
+        }
+        res
+    }
+
+    fn add_origin_headers(&self, origin: &str, res: &mut Response) {
+        let allow_origin: String = self.allow_origin_value(origin);
+        if allow_origin != "*" {
+            res.add_vary("Origin");
+        }
+        res.add_header("Access-Control-Allow-Origin", allow_origin);
+        if self.allow_credentials {
+            res.add_header("Access-Control-Allow-Credentials", "true".to_string());
+        }
+    }
+}
+
+impl Middleware for Cors {
+    fn before(&self, req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        if req.request_line_data().method() != HttpMethod::OPTIONS.to_string() {
+            return None;
+        }
+        let origin: &str = req.get_header("Origin")?;
+        let requested_method: &str = req.get_header("Access-Control-Request-Method")?;
+        Some(self.preflight(origin, requested_method, req.get_header("Access-Control-Request-Headers")))
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        // Responses to other and missing origins lack the CORS headers, so caches must tell them
+        // apart from the responses to allowed origins too.
+        if !self.answers_any_origin() {
+            res.add_vary("Origin");
+        }
+        let origin: &str = match req.get_header("Origin") {
+            Some(origin) if self.is_origin_allowed(origin) => origin,
+            _ => return
+        };
+        self.add_origin_headers(origin, res);
+        if !self.exposed_headers.is_empty() {
+            res.add_header("Access-Control-Expose-Headers", self.exposed_headers.join(", "));
+        }
+    }
+}
+
+/// It matches a string against a pattern, where `*` matches any sequence of characters.
+///
+/// Arguments:
+///
+/// * `pattern`: The pattern, e.g. "https://*.example.com".
+/// * `s`: The string to match.
+///
+/// Returns:
+///
+/// A boolean value.
+fn wildcard_match(pattern: &str, s: &str) -> bool {
+    let pattern: String = pattern.to_ascii_lowercase();
+    let s: String = s.to_ascii_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == s;
+    }
+    let (first, last): (&str, &str) = (parts[0], parts[parts.len() - 1]);
+    if !s.starts_with(first) || !s[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut remaining: &str = &s[first.len()..s.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match remaining.find(part) {
+            Some(index) => { remaining = &remaining[index + part.len()..]; },
+            None => { return false; }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::cors_middleware::{AllowedOrigin, Cors, wildcard_match};
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::util::enums::http_method_enum::HttpMethod;
+
+    fn cors() -> Cors {
+        Cors::new()
+            .allow_origin(AllowedOrigin::Wildcard("https://*.example.com".to_string()))
+            .allow_methods(&[HttpMethod::GET, HttpMethod::POST])
+            .allow_headers(&["Content-Type"])
+            .expose_headers(&["X-Total"])
+            .allow_credentials(true)
+            .max_age(600)
+    }
+
+    #[test]
+    fn answers_preflight() {
+        let mut req: Request = Request::new_without_stream(
+            b"OPTIONS /json/test HTTP/1.1\nOrigin: https://app.example.com\n\
+            Access-Control-Request-Method: POST\nAccess-Control-Request-Headers: content-type", b"").unwrap();
+        let res: Response = cors().before(&mut req, &IocContainer::default()).unwrap();
+        assert_eq!(res.status(), 204);
+        assert_eq!(res.headers().get("Access-Control-Allow-Origin").unwrap(), "https://app.example.com");
+        assert_eq!(res.headers().get("Access-Control-Allow-Methods").unwrap(), "GET, POST");
+        assert_eq!(res.headers().get("Access-Control-Allow-Credentials").unwrap(), "true");
+        assert_eq!(res.headers().get("Access-Control-Max-Age").unwrap(), "600");
+    }
+
+    #[test]
+    fn rejects_disallowed_preflight() {
+        let mut req: Request = Request::new_without_stream(
+            b"OPTIONS / HTTP/1.1\nOrigin: https://app.example.com\nAccess-Control-Request-Method: DELETE",
+            b"").unwrap();
+        assert_eq!(cors().before(&mut req, &IocContainer::default()).unwrap().status(), 403);
+        let mut req: Request = Request::new_without_stream(
+            b"OPTIONS / HTTP/1.1\nOrigin: https://evil.com\nAccess-Control-Request-Method: GET",
+            b"").unwrap();
+        assert_eq!(cors().before(&mut req, &IocContainer::default()).unwrap().status(), 403);
+    }
+
+    #[test]
+    fn decorates_actual_response() {
+        let req: Request = Request::new_without_stream(
+            b"GET / HTTP/1.1\nOrigin: https://app.example.com", b"").unwrap();
+        let mut res: Response = Response::ok();
+        cors().after(&req, &mut res, &IocContainer::default());
+        assert_eq!(res.headers().get("Access-Control-Allow-Origin").unwrap(), "https://app.example.com");
+        assert_eq!(res.headers().get("Access-Control-Expose-Headers").unwrap(), "X-Total");
+        assert_eq!(res.headers().get("Vary").unwrap(), "Origin");
+
+        // Responses to other origins are cached apart from the decorated ones.
+        for head in [&b"GET / HTTP/1.1\nOrigin: https://evil.com"[..], &b"GET / HTTP/1.1"[..]] {
+            let req: Request = Request::new_without_stream(head, b"").unwrap();
+            let mut res: Response = Response::ok();
+            cors().after(&req, &mut res, &IocContainer::default());
+            assert!(res.headers().get("Access-Control-Allow-Origin").is_none());
+            assert_eq!(res.headers().get("Vary").unwrap(), "Origin");
+        }
+        let any: Cors = Cors::new().allow_origin(AllowedOrigin::Any);
+        let mut res: Response = Response::ok();
+        any.after(&req, &mut res, &IocContainer::default());
+        assert_eq!(res.headers().get("Access-Control-Allow-Origin").unwrap(), "*");
+        assert!(res.headers().get("Vary").is_none());
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(wildcard_match("https://*.example.com", "https://a.b.example.com"));
+        assert!(!wildcard_match("https://*.example.com", "https://example.com.evil.com"));
+        assert!(wildcard_match("*", "anything"));
+    }
+}
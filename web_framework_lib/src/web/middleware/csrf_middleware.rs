@@ -0,0 +1,288 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::web::middleware::group_middleware::path_has_prefix;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::cookie::{Cookie, SameSite};
+use crate::web::models::cookie_jar::derive_key;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::sessions::session::Session;
+use crate::web::util::encoders::hmac_signer;
+use crate::web::util::parsers::form_parser::parse_form_urlencoded;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The key the token is stored under in the session.
+const SESSION_KEY: &str = "csrf_token";
+
+/// Where the CSRF token of a client is kept between requests.
+pub enum CsrfStorage {
+    /// In a signed cookie, for applications without sessions. The `Vec<u8>` is the server secret.
+    /// The cookies are signed with a key derived from it for CSRF only, so the secret can be
+    /// shared with `Sessions` and `CookieJar`.
+    Cookie(Vec<u8>),
+    /// In the `Session`, which requires the `Sessions` middleware to be added before `Csrf`.
+    Session
+}
+
+/// `CsrfToken` is the token of the current client, attached to every request passing `Csrf`.
+/// Handlers and templates embed it into forms with `hidden_input`, or hand it to scripts, which
+/// send it back in the header.
+///
+/// Properties:
+///
+/// * `value`: The token.
+/// * `field_name`: The name of the form field the token is expected in.
+/// * `is_new`: If the token was created during this request, and still has to be stored.
+#[derive(Debug, Clone)]
+pub struct CsrfToken {
+    value: String,
+    field_name: String,
+    is_new: bool
+}
+
+impl CsrfToken {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
+    /// It renders the token as a hidden form field, e.g.
+    /// `<input type="hidden" name="csrf_token" value="...">`.
+    pub fn hidden_input(&self) -> String {
+        format!("<input type=\"hidden\" name=\"{}\" value=\"{}\">", self.field_name, self.value)
+    }
+}
+
+/// `Csrf` is middleware protecting against cross-site request forgery. Every client gets a random
+/// token, kept in a signed cookie or in its session. Requests with an unsafe method (POST, PUT,
+/// PATCH or DELETE) must send the token back in a form field or a header, otherwise they are
+/// rejected with `403 Forbidden`. Paths added with `exempt`, e.g. webhooks, are not checked.
+///
+/// Properties:
+///
+/// * `storage`: Where the token is kept.
+/// * `field_name`: The form field the token is read from.
+/// * `header_name`: The header the token is read from.
+/// * `cookie_name`: The name of the cookie, if the token is kept in a cookie.
+/// * `secure`: The `Secure` attribute of the cookie.
+/// * `exempt`: Path prefixes that are not checked.
+pub struct Csrf {
+    storage: CsrfStorage,
+    field_name: String,
+    header_name: String,
+    cookie_name: String,
+    secure: bool,
+    exempt: Vec<String>
+}
+
+impl Csrf {
+    /// `new` creates the middleware, reading the token from the "csrf_token" form field or the
+    /// "X-CSRF-Token" header.
+    ///
+    /// Arguments:
+    ///
+    /// * `storage`: Where the token is kept.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Csrf struct.
+    pub fn new(storage: CsrfStorage) -> Self {
+        let storage: CsrfStorage = match storage {
+            CsrfStorage::Cookie(secret) => CsrfStorage::Cookie(derive_key(&secret, "csrf-signing").to_vec()),
+            storage => storage
+        };
+        Self {
+            storage,
+            field_name: "csrf_token".to_string(),
+            header_name: "X-CSRF-Token".to_string(),
+            cookie_name: "csrf_token".to_string(),
+            secure: true,
+            exempt: Vec::new()
+        }
+    }
+
+    pub fn field_name(mut self, field_name: &str) -> Self {
+        self.field_name = field_name.to_string();
+        self
+    }
+    pub fn header_name(mut self, header_name: &str) -> Self {
+        self.header_name = header_name.to_string();
+        self
+    }
+    pub fn cookie_name(mut self, cookie_name: &str) -> Self {
+        self.cookie_name = cookie_name.to_string();
+        self
+    }
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// It exempts a path and everything under it from the check, e.g. "/webhooks".
+    pub fn exempt(mut self, prefix: &str) -> Self {
+        self.exempt.push(prefix.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// It loads the stored token of the client.
+    fn stored_token(&self, req: &Request) -> Option<String> {
+        match &self.storage {
+            CsrfStorage::Cookie(secret) => req.cookie(&self.cookie_name)
+                .and_then(|cookie: &str| hmac_signer::verify(secret, cookie))
+                .map(|token: &str| token.to_string()),
+            CsrfStorage::Session => req.session()
+                .and_then(|session: &Session| session.get(SESSION_KEY))
+        }
+    }
+
+    /// It finds the token the client sent back, in the header or the form field.
+    fn submitted_token(&self, req: &Request) -> Option<String> {
+        if let Some(token) = req.get_header(&self.header_name) {
+            return Some(token.trim().to_string());
+        }
+        let is_form: bool = req.get_header("Content-Type")
+            .is_some_and(|content_type: &str| content_type.to_ascii_lowercase()
+                .starts_with("application/x-www-form-urlencoded"));
+        if !is_form {
+            return None;
+        }
+        parse_form_urlencoded(req.body()).into_iter()
+            .find(|(name, _)| *name == self.field_name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// It creates a new random token.
+fn new_token() -> String {
+    let mut bytes: [u8; 32] = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// It compares two tokens in constant time, so timing doesn't reveal how much of a guess is right.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff: u8, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// It checks if the method can change state, and so must be protected.
+fn is_unsafe_method(method: &str) -> bool {
+    matches!(method, "POST" | "PUT" | "PATCH" | "DELETE")
+}
+
+impl Middleware for Csrf {
+    fn before(&self, req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        let stored: Option<String> = self.stored_token(req);
+        let path: &str = req.request_line_data().path();
+        // The normalised path is checked, like group middleware does, so both agree on what's under a prefix.
+        let exempt: bool = req.request_line_data().normalized_path().is_some_and(|normalized: &str|
+            self.exempt.iter().any(|prefix: &String| path_has_prefix(prefix, normalized)));
+        if !exempt && is_unsafe_method(req.request_line_data().method()) {
+            let valid: bool = match (&stored, self.submitted_token(req)) {
+                (Some(stored), Some(submitted)) => tokens_match(stored, &submitted),
+                _ => false
+            };
+            if !valid {
+                if log::log_enabled!(log::Level::Warn) {
+                    log::warn!("Rejected request with missing or wrong CSRF token, path: {}", path);
+                }
+                return Some(fallback_handlers::negotiated_error_response(Some(req), StatusCode::FORBIDDEN));
+            }
+        }
+        let token: CsrfToken = match stored {
+            Some(value) => CsrfToken { value, field_name: self.field_name.clone(), is_new: false },
+            None => CsrfToken { value: new_token(), field_name: self.field_name.clone(), is_new: true }
+        };
+        if let (CsrfStorage::Session, true) = (&self.storage, token.is_new) {
+            match req.session() {
+                Some(session) => { session.insert(SESSION_KEY, &token.value); },
+                None => log::error!("Csrf with CsrfStorage::Session requires the Sessions middleware")
+            }
+        }
+        req.extensions_mut().insert(token);
+        None
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        if let (CsrfStorage::Cookie(secret), Some(token)) = (&self.storage, req.csrf_token()) {
+            if token.is_new {
+                res.set_cookie(Cookie::new(&self.cookie_name, &hmac_signer::sign(secret, &token.value))
+                    .path("/")
+                    .secure(self.secure)
+                    .http_only(true)
+                    .same_site(SameSite::Lax));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::csrf_middleware::{Csrf, CsrfStorage};
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::util::encoders::hmac_signer;
+
+    fn request(head: &str, body: &str) -> Request {
+        Request::new_without_stream(head.as_bytes(), body.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn issues_and_verifies_cookie_tokens() {
+        let csrf: Csrf = Csrf::new(CsrfStorage::Cookie(b"secret".to_vec())).exempt("/webhooks");
+        let container: IocContainer = IocContainer::default();
+
+        let mut form: Request = request("GET /form HTTP/1.1\nHost: a", "");
+        assert!(csrf.before(&mut form, &container).is_none());
+        let token: String = form.csrf_token().unwrap().value().to_string();
+        assert!(form.csrf_token().unwrap().hidden_input().contains(&token));
+        let mut res: Response = Response::ok();
+        csrf.after(&form, &mut res, &container);
+        let cookie: String = res.cookies()[0].to_string();
+        let cookie: &str = cookie.split(';').next().unwrap();
+        // The cookie isn't signed with the raw secret, which `Sessions` signs its cookies with.
+        let value: &str = cookie.split_once('=').unwrap().1;
+        assert!(hmac_signer::verify(b"secret", value).is_none());
+        let mut session_signed: Request = request(
+            &format!("POST /form HTTP/1.1\nCookie: csrf_token={}\nX-CSRF-Token: {}", hmac_signer::sign(b"secret", &token), token), "");
+        assert_eq!(csrf.before(&mut session_signed, &container).unwrap().status(), 403);
+
+        let mut post: Request = request(
+            &format!("POST /form HTTP/1.1\nCookie: {}\nContent-Type: application/x-www-form-urlencoded", cookie),
+            &format!("name=a&csrf_token={}", token));
+        assert!(csrf.before(&mut post, &container).is_none());
+        let mut res: Response = Response::ok();
+        csrf.after(&post, &mut res, &container);
+        assert!(res.cookies().is_empty());
+
+        let mut header: Request = request(
+            &format!("DELETE /items/1 HTTP/1.1\nCookie: {}\nX-CSRF-Token: {}", cookie, token), "");
+        assert!(csrf.before(&mut header, &container).is_none());
+
+        let mut forged: Request = request(
+            &format!("POST /form HTTP/1.1\nCookie: {}\nX-CSRF-Token: {}x", cookie, token), "");
+        assert_eq!(csrf.before(&mut forged, &container).unwrap().status(), 403);
+        let mut missing: Request = request("PUT /form HTTP/1.1\nHost: a", "");
+        assert_eq!(csrf.before(&mut missing, &container).unwrap().status(), 403);
+        let mut webhook: Request = request("POST /webhooks/payment HTTP/1.1\nHost: a", "");
+        assert!(csrf.before(&mut webhook, &container).is_none());
+    }
+}
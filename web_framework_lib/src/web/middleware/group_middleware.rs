@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `GroupMiddleware` applies middleware only to the requests under a path prefix, e.g. every
+/// route under "/admin". It is added with `RouteHandlerContainer::add_group_middleware`, and runs
+/// with the global middleware, so it also covers routes inserted later.
+///
+/// Properties:
+///
+/// * `prefix`: The path prefix of the group, e.g. "/admin".
+/// * `middleware`: The middleware to apply to the group.
+pub struct GroupMiddleware {
+    prefix: String,
+    middleware: Arc<dyn Middleware>
+}
+
+impl GroupMiddleware {
+    pub fn new(prefix: &str, middleware: Arc<dyn Middleware>) -> Self {
+        Self { prefix: prefix.trim_end_matches('/').to_string(), middleware }
+    }
+
+    /// It checks if a path is in the group. "/admin" contains "/admin" and "/admin/users", but
+    /// not "/administrator".
+    pub fn contains(&self, path: &str) -> bool {
+        path_has_prefix(&self.prefix, path)
+    }
+
+    /// It checks if a request is in the group, by its normalised path, so "//admin" and
+    /// "/%61dmin" are in "/admin" too. A path that can't be normalised counts as in the group,
+    /// so e.g. an auth guard isn't skipped for it.
+    fn contains_request(&self, req: &Request) -> bool {
+        req.request_line_data().normalized_path().is_none_or(|path: &str| self.contains(path))
+    }
+}
+
+/// It checks if a path is the prefix or lies under it, by whole path segments.
+///
+/// Arguments:
+///
+/// * `prefix`: The prefix without a trailing slash, e.g. "/admin".
+/// * `path`: The path to check.
+pub(crate) fn path_has_prefix(prefix: &str, path: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest: &str| rest.is_empty() || rest.starts_with('/'))
+}
+
+impl Middleware for GroupMiddleware {
+    fn before(&self, req: &mut Request, container: &IocContainer) -> Option<Response> {
+        if self.contains_request(req) {
+            self.middleware.before(req, container)
+        } else {
+            None
+        }
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, container: &IocContainer) {
+        if self.contains_request(req) {
+            self.middleware.after(req, res, container);
+        }
+    }
+}
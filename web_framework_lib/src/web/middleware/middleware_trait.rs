@@ -0,0 +1,51 @@
+use di_ioc_lib::di::ioc_container::IocContainer;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `Middleware` wraps request handling with logic shared by many routes, like authentication,
+/// logging or CORS. Middleware is added globally with `RouteHandlerContainer::add_middleware`, or
+/// per route with `RouteHandlerContainer::add_route_middleware`.
+///
+/// For a request, the `before` functions run in the order the middleware was added, global
+/// middleware first. Then the handler runs, and then the `after` functions run in reverse order.
+/// If a `before` returns a response, the handler and the remaining middleware are skipped, and
+/// only the `after` functions of the middleware that ran before it are called.
+///
+/// Global middleware also wraps the fallback handlers and static resources, route middleware only
+/// wraps the handler of its route.
+pub trait Middleware: Send + Sync {
+    /// Called before the handler. It can inspect or modify the request, or short-circuit by
+    /// returning a response.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request being handled.
+    /// * `container`: The container the server was started with.
+    ///
+    /// Returns:
+    ///
+    /// `None` to continue, or the response to resolve the request with.
+    fn before(&self, _req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        None
+    }
+
+    /// Called after the handler, with the response it returned.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request being handled.
+    /// * `res`: The response to post-process.
+    /// * `container`: The container the server was started with.
+    fn after(&self, _req: &Request, _res: &mut Response, _container: &IocContainer) { }
+}
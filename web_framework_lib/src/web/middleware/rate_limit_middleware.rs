@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use di_ioc_lib::di::ioc_container::IocContainer;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::request_handling::route::Route;
+use crate::web::request_handling::route_handler_container::RouteHandlerContainer;
+use crate::web::util::enums::http_method_enum::HttpMethod;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// What requests are grouped by when counting them.
+pub enum RateLimitKey {
+    /// The IP address of the client.
+    ClientIp,
+    /// The value of a request header, e.g. an API key, or `X-Forwarded-For` behind a proxy.
+    /// Requests without the header are grouped by client IP instead. The header must hold a value
+    /// the server verifies, e.g. an API key checked by earlier middleware, or one set by a trusted
+    /// proxy, otherwise clients get a fresh bucket by sending a new value.
+    Header(String),
+    /// The path pattern of the matched route, e.g. "/users/{id}", so all clients share one limit
+    /// per route. Requests matching no route are grouped by client IP instead, so random paths
+    /// don't create buckets.
+    Route
+}
+
+/// `RateLimitStatus` is attached to every request passing a `RateLimiter`, so `after` can add the
+/// `RateLimit-*` headers, and handlers can see how much of the limit is left.
+///
+/// Properties:
+///
+/// * `limit`: How many requests fit in the bucket.
+/// * `remaining`: How many requests are left right now.
+/// * `reset`: How long until the bucket is full again.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitStatus {
+    limit: u32,
+    remaining: u32,
+    reset: Duration
+}
+
+impl RateLimitStatus {
+    pub fn limit(&self) -> u32 {
+        self.limit
+    }
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+    pub fn reset(&self) -> Duration {
+        self.reset
+    }
+}
+
+/// The key of the bucket shared by new keys once there are `max_buckets`.
+const OVERFLOW_KEY: &str = "overflow";
+
+/// A token bucket. It holds up to `limit` tokens, refills continuously, and every request takes
+/// one token.
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant
+}
+
+/// `RateLimiter` is middleware limiting how fast clients can make requests, using a token bucket
+/// per key. A client can burst up to `limit` requests, after which it can make one request every
+/// `period / limit`. Rejected requests get a `429 Too Many Requests` with a `Retry-After` header.
+///
+/// Buckets live in memory. Buckets that have refilled completely are removed every `period`, since
+/// they are no different from a new bucket. There are at most `max_buckets`, once they are all in
+/// use, requests with new keys share one more bucket, so flooding the limiter with new keys
+/// neither exhausts memory nor gets around the limit.
+///
+/// Add it globally with `RouteHandlerContainer::add_middleware`, or give a route its own limit with
+/// `RouteHandlerContainer::add_route_middleware`.
+///
+/// Properties:
+///
+/// * `key`: What requests are grouped by.
+/// * `limit`: The size of the bucket.
+/// * `period`: How long an empty bucket takes to refill completely.
+/// * `max_buckets`: How many buckets there can be. 100000 by default.
+/// * `buckets`: The buckets, keyed by the value of `key`.
+/// * `last_cleanup`: When idle buckets were last removed.
+pub struct RateLimiter {
+    key: RateLimitKey,
+    limit: u32,
+    period: Duration,
+    max_buckets: usize,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    last_cleanup: Mutex<Instant>
+}
+
+impl RateLimiter {
+    /// `new` creates a rate limiter allowing `limit` requests per `period` for every key.
+    ///
+    /// Arguments:
+    ///
+    /// * `key`: What requests are grouped by.
+    /// * `limit`: How many requests a key can make in a burst, at least 1.
+    /// * `period`: How long it takes to earn back the whole burst.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the RateLimiter struct.
+    pub fn new(key: RateLimitKey, limit: u32, period: Duration) -> Self {
+        Self {
+            key,
+            limit: limit.max(1),
+            period,
+            max_buckets: 100_000,
+            buckets: Mutex::new(HashMap::new()),
+            last_cleanup: Mutex::new(Instant::now())
+        }
+    }
+
+    /// It sets how many buckets there can be, at least 1.
+    pub fn max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets.max(1);
+        self
+    }
+
+    /// How many tokens are refilled per second.
+    fn refill_rate(&self) -> f64 {
+        self.limit as f64 / self.period.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// It finds the key a request is counted under. Global limiters run before routing, so the
+    /// route is looked up here if the request wasn't matched yet.
+    fn key_of(&self, req: &Request, container: &IocContainer) -> String {
+        let client_ip = || req.peer_addr()
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        match &self.key {
+            RateLimitKey::ClientIp => client_ip(),
+            RateLimitKey::Header(name) => req.get_header(name)
+                .map(|value: &str| value.to_string())
+                .unwrap_or_else(client_ip),
+            RateLimitKey::Route => req.route_pattern()
+                .or_else(|| {
+                    let method: HttpMethod = HttpMethod::from_str(req.request_line_data().method()).ok()?;
+                    container.get_ref::<RouteHandlerContainer>().ok()?
+                        .get_route(req.request_line_data().path(), &method)
+                        .map(|route: &Route| route.path())
+                })
+                .map(|pattern: &str| pattern.to_string())
+                .unwrap_or_else(|| format!("unmatched {}", client_ip()))
+        }
+    }
+
+    /// It takes a token from the key's bucket, if there is one.
+    ///
+    /// Arguments:
+    ///
+    /// * `key`: The key the request is counted under.
+    /// * `now`: The current time.
+    ///
+    /// Returns:
+    ///
+    /// Ok with the status after taking the token, or Err with the status and how long until a
+    /// token is available.
+    fn acquire(&self, key: &str, now: Instant) -> Result<RateLimitStatus, (RateLimitStatus, Duration)> {
+        self.cleanup(now);
+        let limit: f64 = self.limit as f64;
+        let rate: f64 = self.refill_rate();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let key: &str = if buckets.len() >= self.max_buckets && !buckets.contains_key(key) {
+            self.remove_refilled(&mut buckets, now);
+            if buckets.len() >= self.max_buckets { OVERFLOW_KEY } else { key }
+        } else {
+            key
+        };
+        let bucket: &mut Bucket = buckets.entry(key.to_string())
+            .or_insert(Bucket { tokens: limit, last_refill: now });
+        let elapsed: f64 = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(limit);
+        bucket.last_refill = now;
+        let allowed: bool = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let status: RateLimitStatus = RateLimitStatus {
+            limit: self.limit,
+            remaining: bucket.tokens.floor() as u32,
+            reset: Duration::from_secs_f64((limit - bucket.tokens) / rate)
+        };
+        if allowed {
+            Ok(status)
+        } else {
+            Err((status, Duration::from_secs_f64((1.0 - bucket.tokens) / rate)))
+        }
+    }
+
+    /// Once every `period`, it removes the buckets that have refilled completely.
+    fn cleanup(&self, now: Instant) {
+        let mut last_cleanup = self.last_cleanup.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(*last_cleanup) < self.period {
+            return;
+        }
+        *last_cleanup = now;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        self.remove_refilled(&mut buckets, now);
+    }
+
+    /// It removes the buckets that have refilled completely.
+    fn remove_refilled(&self, buckets: &mut HashMap<String, Bucket>, now: Instant) {
+        let limit: f64 = self.limit as f64;
+        let rate: f64 = self.refill_rate();
+        let before: usize = buckets.len();
+        buckets.retain(|_, bucket: &mut Bucket| {
+            let elapsed: f64 = now.saturating_duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens + elapsed * rate < limit
+        });
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("Removed {} idle rate limit buckets", before - buckets.len());
+        }
+    }
+}
+
+/// It adds the `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers.
+fn add_rate_limit_headers(res: &mut Response, status: &RateLimitStatus) {
+    res.add_header("RateLimit-Limit", status.limit.to_string());
+    res.add_header("RateLimit-Remaining", status.remaining.to_string());
+    res.add_header("RateLimit-Reset", seconds_rounded_up(status.reset).to_string());
+}
+
+fn seconds_rounded_up(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+impl Middleware for RateLimiter {
+    fn before(&self, req: &mut Request, container: &IocContainer) -> Option<Response> {
+        let key: String = self.key_of(req, container);
+        match self.acquire(&key, Instant::now()) {
+            Ok(status) => {
+                req.extensions_mut().insert(status);
+                None
+            },
+            Err((status, retry_after)) => {
+                if log::log_enabled!(log::Level::Warn) {
+                    log::warn!("Rate limited: {}, path: {}", key, req.request_line_data().path());
+                }
+                let mut res: Response = fallback_handlers::negotiated_error_response(
+                    Some(req), StatusCode::TOO_MANY_REQUESTS);
+                res.add_header("Retry-After", seconds_rounded_up(retry_after).max(1).to_string());
+                add_rate_limit_headers(&mut res, &status);
+                Some(res)
+            }
+        }
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        if let Some(status) = req.extension::<RateLimitStatus>() {
+            if !res.headers().contains_key("RateLimit-Limit") {
+                add_rate_limit_headers(res, status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::middleware::rate_limit_middleware::{RateLimiter, RateLimitKey};
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::request_handling::route_handler_container::RouteHandlerContainer;
+    use crate::web::util::enums::http_method_enum::HttpMethod;
+
+    #[test]
+    fn rejects_after_burst() {
+        let limiter: RateLimiter = RateLimiter::new(
+            RateLimitKey::Header("X-Api-Key".to_string()), 2, Duration::from_secs(60));
+        let container: IocContainer = IocContainer::default();
+        let mut req: Request = Request::new_without_stream(
+            b"GET /search HTTP/1.1\nX-Api-Key: abc", b"").unwrap();
+        assert!(limiter.before(&mut req, &container).is_none());
+        assert!(limiter.before(&mut req, &container).is_none());
+        let mut ok: Response = Response::ok();
+        limiter.after(&req, &mut ok, &container);
+        assert_eq!(ok.headers().get("RateLimit-Remaining").unwrap(), "0");
+        let rejected: Response = limiter.before(&mut req, &container).unwrap();
+        assert_eq!(rejected.status(), 429);
+        assert_eq!(rejected.headers().get("Retry-After").unwrap(), "30");
+        assert_eq!(rejected.headers().get("RateLimit-Limit").unwrap(), "2");
+
+        let mut other: Request = Request::new_without_stream(
+            b"GET /search HTTP/1.1\nX-Api-Key: def", b"").unwrap();
+        assert!(limiter.before(&mut other, &container).is_none());
+    }
+
+    #[test]
+    fn refills_and_cleans_up() {
+        let limiter: RateLimiter = RateLimiter::new(RateLimitKey::Route, 1, Duration::from_secs(10));
+        let start: Instant = Instant::now();
+        assert!(limiter.acquire("/login", start).is_ok());
+        assert!(limiter.acquire("/login", start + Duration::from_secs(5)).is_err());
+        assert!(limiter.acquire("/login", start + Duration::from_secs(10)).is_ok());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+        let _ = limiter.acquire("/search", start + Duration::from_secs(30));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    fn handler(_req: &Request) -> Response {
+        Response::ok()
+    }
+
+    #[test]
+    fn keys_routes_by_pattern() {
+        let limiter: RateLimiter = RateLimiter::new(RateLimitKey::Route, 1, Duration::from_secs(60));
+        let mut rhc: RouteHandlerContainer = RouteHandlerContainer::new();
+        rhc.insert("/users/{id}", handler, HttpMethod::GET);
+        let mut container: IocContainer = IocContainer::default();
+        container.install_reference_provider(Arc::new(rhc));
+        let mut first: Request = Request::new_without_stream(b"GET /users/1 HTTP/1.1", b"").unwrap();
+        assert!(limiter.before(&mut first, &container).is_none());
+        // Every user shares the bucket of the route.
+        let mut second: Request = Request::new_without_stream(b"GET /users/2 HTTP/1.1", b"").unwrap();
+        assert_eq!(limiter.before(&mut second, &container).unwrap().status(), 429);
+        // Requests matching no route are counted per client, whatever their path.
+        let mut missing: Request = Request::new_without_stream(b"GET /missing HTTP/1.1", b"").unwrap();
+        assert!(limiter.before(&mut missing, &container).is_none());
+        let mut other: Request = Request::new_without_stream(b"GET /other HTTP/1.1", b"").unwrap();
+        assert_eq!(limiter.before(&mut other, &container).unwrap().status(), 429);
+        assert!(limiter.buckets.lock().unwrap().contains_key("/users/{id}"));
+        assert!(limiter.buckets.lock().unwrap().contains_key("unmatched unknown"));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn caps_buckets() {
+        let limiter: RateLimiter = RateLimiter::new(
+            RateLimitKey::Header("X-Api-Key".to_string()), 1, Duration::from_secs(60)).max_buckets(2);
+        let start: Instant = Instant::now();
+        assert!(limiter.acquire("a", start).is_ok());
+        assert!(limiter.acquire("b", start).is_ok());
+        // New keys share one bucket once the buckets are used up.
+        assert!(limiter.acquire("c", start).is_ok());
+        assert!(limiter.acquire("d", start).is_err());
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 3);
+        // Refilled buckets make room for new keys.
+        assert!(limiter.acquire("e", start + Duration::from_secs(60)).is_ok());
+        assert!(limiter.buckets.lock().unwrap().contains_key("e"));
+    }
+}
@@ -0,0 +1,208 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The placeholder in the `Content-Security-Policy` replaced with the nonce of the request.
+pub const NONCE_PLACEHOLDER: &str = "{nonce}";
+
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self' 'nonce-{nonce}'; \
+    style-src 'self' 'nonce-{nonce}'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+
+/// `CspNonce` is a random value attached to every request passing `SecurityHeaders`, and put in
+/// the `Content-Security-Policy` in place of `{nonce}`. Inline scripts and styles carrying it in
+/// their `nonce` attribute are allowed to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    pub fn value(&self) -> &str {
+        &self.0
+    }
+
+    /// It returns the nonce as an HTML attribute, e.g. `nonce="..."`, for `<script>` and `<style>`.
+    pub fn attribute(&self) -> String {
+        format!("nonce=\"{}\"", self.0)
+    }
+}
+
+/// The address of the `SecurityHeaders` whose `before` ran last, which is the route's own instance
+/// if there is one, so only that instance adds headers.
+struct ActiveSecurityHeaders(usize);
+
+/// `SecurityHeaders` is middleware adding security headers to every response. The defaults are:
+///
+/// * `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+/// * `Content-Security-Policy`: only same-origin resources, and inline scripts and styles with the
+///   nonce of the request.
+/// * `X-Content-Type-Options: nosniff`
+/// * `X-Frame-Options: DENY`
+/// * `Referrer-Policy: strict-origin-when-cross-origin`
+/// * `Permissions-Policy: camera=(), microphone=(), geolocation=()`
+///
+/// Headers already set by the handler are kept. Add it globally with
+/// `RouteHandlerContainer::add_middleware`. To override headers for a route, add another instance
+/// with `RouteHandlerContainer::add_route_middleware`, which then replaces the global one for that
+/// route.
+///
+/// Properties:
+///
+/// * `headers`: The headers to add, in order.
+pub struct SecurityHeaders {
+    headers: Vec<(&'static str, String)>
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecurityHeaders {
+    pub fn new() -> Self {
+        Self {
+            headers: vec![
+                ("Strict-Transport-Security", "max-age=31536000; includeSubDomains".to_string()),
+                ("Content-Security-Policy", DEFAULT_CONTENT_SECURITY_POLICY.to_string()),
+                ("X-Content-Type-Options", "nosniff".to_string()),
+                ("X-Frame-Options", "DENY".to_string()),
+                ("Referrer-Policy", "strict-origin-when-cross-origin".to_string()),
+                ("Permissions-Policy", "camera=(), microphone=(), geolocation=()".to_string())
+            ]
+        }
+    }
+
+    /// It sets a header, or removes it with `None`.
+    fn set(mut self, name: &'static str, value: Option<&str>) -> Self {
+        match (self.headers.iter_mut().find(|(current, _)| *current == name), value) {
+            (Some(header), Some(value)) => header.1 = value.to_string(),
+            (None, Some(value)) => self.headers.push((name, value.to_string())),
+            (_, None) => self.headers.retain(|(current, _)| *current != name)
+        }
+        self
+    }
+
+    /// It sets `Strict-Transport-Security`, e.g. "max-age=63072000; includeSubDomains; preload".
+    pub fn strict_transport_security(self, value: Option<&str>) -> Self {
+        self.set("Strict-Transport-Security", value)
+    }
+
+    /// It sets `Content-Security-Policy`. Every `{nonce}` is replaced with the nonce of the request.
+    pub fn content_security_policy(self, value: Option<&str>) -> Self {
+        self.set("Content-Security-Policy", value)
+    }
+
+    /// It sets `X-Content-Type-Options`.
+    pub fn content_type_options(self, value: Option<&str>) -> Self {
+        self.set("X-Content-Type-Options", value)
+    }
+
+    /// It sets `X-Frame-Options`, e.g. "SAMEORIGIN" for pages embedded by the site itself.
+    pub fn frame_options(self, value: Option<&str>) -> Self {
+        self.set("X-Frame-Options", value)
+    }
+
+    /// It sets `Referrer-Policy`, e.g. "no-referrer".
+    pub fn referrer_policy(self, value: Option<&str>) -> Self {
+        self.set("Referrer-Policy", value)
+    }
+
+    /// It sets `Permissions-Policy`.
+    pub fn permissions_policy(self, value: Option<&str>) -> Self {
+        self.set("Permissions-Policy", value)
+    }
+
+    fn address(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn uses_nonce(&self) -> bool {
+        self.headers.iter().any(|(_, value)| value.contains(NONCE_PLACEHOLDER))
+    }
+}
+
+impl Middleware for SecurityHeaders {
+    fn before(&self, req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        if self.uses_nonce() && req.csp_nonce().is_none() {
+            let mut bytes: [u8; 16] = [0; 16];
+            OsRng.fill_bytes(&mut bytes);
+            req.extensions_mut().insert(CspNonce(STANDARD.encode(bytes)));
+        }
+        req.extensions_mut().insert(ActiveSecurityHeaders(self.address()));
+        None
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        if req.extension::<ActiveSecurityHeaders>().is_some_and(|active| active.0 != self.address()) {
+            return;
+        }
+        for (name, value) in &self.headers {
+            if res.headers().contains_key(name) {
+                continue;
+            }
+            let value: String = match req.csp_nonce() {
+                Some(nonce) => value.replace(NONCE_PLACEHOLDER, nonce.value()),
+                None => value.clone()
+            };
+            res.add_header(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::middleware::security_headers_middleware::SecurityHeaders;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+
+    #[test]
+    fn adds_defaults_with_nonce() {
+        let headers: SecurityHeaders = SecurityHeaders::new();
+        let container: IocContainer = IocContainer::default();
+        let mut req: Request = Request::new_without_stream(b"GET / HTTP/1.1", b"").unwrap();
+        assert!(headers.before(&mut req, &container).is_none());
+        let nonce: String = req.csp_nonce().unwrap().value().to_string();
+        let mut res: Response = Response::ok();
+        res.add_header("X-Frame-Options", "SAMEORIGIN".to_string());
+        headers.after(&req, &mut res, &container);
+        let csp: &str = res.headers().get("Content-Security-Policy").unwrap();
+        assert!(csp.contains(&format!("script-src 'self' 'nonce-{}'", nonce)));
+        assert_eq!(res.headers().get("X-Content-Type-Options").unwrap(), "nosniff");
+        assert_eq!(res.headers().get("X-Frame-Options").unwrap(), "SAMEORIGIN");
+        assert!(res.headers().contains_key("Strict-Transport-Security"));
+    }
+
+    #[test]
+    fn route_instance_replaces_global() {
+        let global: SecurityHeaders = SecurityHeaders::new();
+        let route: SecurityHeaders = SecurityHeaders::new()
+            .frame_options(None)
+            .content_security_policy(Some("frame-ancestors https://partner.example"));
+        let container: IocContainer = IocContainer::default();
+        let mut req: Request = Request::new_without_stream(b"GET /embed HTTP/1.1", b"").unwrap();
+        assert!(global.before(&mut req, &container).is_none());
+        assert!(route.before(&mut req, &container).is_none());
+        let mut res: Response = Response::ok();
+        route.after(&req, &mut res, &container);
+        global.after(&req, &mut res, &container);
+        assert!(!res.headers().contains_key("X-Frame-Options"));
+        assert_eq!(res.headers().get("Content-Security-Policy").unwrap(), "frame-ancestors https://partner.example");
+    }
+}
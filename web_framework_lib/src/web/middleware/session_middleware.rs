@@ -0,0 +1,354 @@
+use std::sync::{Arc, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use di_ioc_lib::di::ioc_container::IocContainer;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::models::cookie::{Cookie, SameSite};
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::sessions::session::{Session, SessionData, SessionState};
+use crate::web::sessions::session_store::SessionStore;
+use crate::web::util::encoders::hmac_signer;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `Sessions` is middleware giving every request a `Session`. The session id travels in a cookie
+/// signed with HMAC-SHA256, so clients can't forge ids, and the data stays in the `SessionStore`.
+/// Sessions are only saved, and the cookie only set, once they hold data, and then only by
+/// requests that change them, so a request that only read a session can't overwrite what a
+/// concurrent one saved. Other requests extend the session with `SessionStore::touch`, and
+/// refresh the cookie once it's older than half of `max_age`, so it isn't sent with every response.
+/// The cookie holds the id and when it was issued, both signed.
+///
+/// Properties:
+///
+/// * `store`: Where the session data is kept.
+/// * `secret`: The key the session cookie is signed with.
+/// * `cookie_name`: The name of the session cookie.
+/// * `path`: The `Path` attribute of the cookie.
+/// * `secure`: The `Secure` attribute, so the cookie is only sent over HTTPS.
+/// * `http_only`: The `HttpOnly` attribute, so scripts can't read the cookie.
+/// * `same_site`: The `SameSite` attribute.
+/// * `max_age`: How long sessions and their cookies live without a request.
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    secret: Vec<u8>,
+    cookie_name: String,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    same_site: SameSite,
+    max_age: Duration
+}
+
+impl Sessions {
+    /// `new` creates the middleware with a `Secure`, `HttpOnly`, `SameSite=Lax` cookie named
+    /// "session_id", and sessions living a day.
+    ///
+    /// Arguments:
+    ///
+    /// * `store`: Where the session data is kept.
+    /// * `secret`: The key the session cookie is signed with, at least 32 random bytes.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Sessions struct.
+    pub fn new<S: SessionStore + 'static>(store: S, secret: &[u8]) -> Self {
+        Self {
+            store: Arc::new(store),
+            secret: secret.to_vec(),
+            cookie_name: "session_id".to_string(),
+            path: "/".to_string(),
+            secure: true,
+            http_only: true,
+            same_site: SameSite::Lax,
+            max_age: Duration::from_secs(24 * 60 * 60)
+        }
+    }
+
+    pub fn cookie_name(mut self, cookie_name: &str) -> Self {
+        self.cookie_name = cookie_name.to_string();
+        self
+    }
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// It creates the session cookie for a session id, or a cookie removing it for `None`.
+    fn session_cookie(&self, id: Option<&str>) -> Cookie {
+        let cookie: Cookie = match id {
+            Some(id) => {
+                let value: String = format!("{}.{}", id, unix_now());
+                Cookie::new(&self.cookie_name, &hmac_signer::sign(&self.secret, &value))
+                    .max_age(self.max_age)
+            },
+            None => Cookie::removal(&self.cookie_name)
+        };
+        cookie.path(&self.path)
+            .secure(self.secure)
+            .http_only(self.http_only)
+            .same_site(self.same_site)
+    }
+
+    /// It reads the session id from the request's cookie, if the signature is valid.
+    ///
+    /// Returns:
+    ///
+    /// The session id, and when the cookie was issued, in seconds since the epoch. Cookies
+    /// without that time count as issued at the epoch, so they are refreshed.
+    fn read_cookie<'a>(&self, req: &'a Request) -> Option<(&'a str, u64)> {
+        let value: &str = hmac_signer::verify(&self.secret, req.cookie(&self.cookie_name)?)?;
+        Some(match value.split_once('.') {
+            Some((id, issued)) => (id, issued.parse().ok()?),
+            None => (value, 0)
+        })
+    }
+
+    /// It loads the session of the request's cookie, if the signature is valid and the session
+    /// still exists.
+    fn load(&self, req: &Request) -> Option<Session> {
+        let (id, _) = self.read_cookie(req)?;
+        self.store.load(id).map(|data: SessionData| Session::existing(id, data))
+    }
+
+    /// It extends a session the request didn't change, without saving its data, and refreshes
+    /// the cookie once it's older than half of `max_age`. Before that, nothing is done, so most
+    /// requests neither touch the store nor get a `Set-Cookie`.
+    fn extend(&self, req: &Request, res: &mut Response, id: &str) {
+        let issued: u64 = self.read_cookie(req).map_or(0, |(_, issued)| issued);
+        if unix_now().saturating_sub(issued) < self.max_age.as_secs() / 2 {
+            return;
+        }
+        match self.store.touch(id, self.max_age) {
+            Ok(()) => {
+                res.set_cookie(self.session_cookie(Some(id)));
+            },
+            Err(e) => log::error!("Failed to extend session: {}", e)
+        }
+    }
+}
+
+/// The current time in seconds since the epoch.
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d: Duration| d.as_secs()).unwrap_or(0)
+}
+
+/// It creates a new random session id.
+fn new_session_id() -> String {
+    let mut bytes: [u8; 32] = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl Middleware for Sessions {
+    fn before(&self, req: &mut Request, _container: &IocContainer) -> Option<Response> {
+        let session: Session = self.load(req).unwrap_or_default();
+        req.extensions_mut().insert(session);
+        None
+    }
+
+    fn after(&self, req: &Request, res: &mut Response, _container: &IocContainer) {
+        let session: &Session = match req.session() {
+            Some(session) => session,
+            None => return
+        };
+        let mut state: MutexGuard<SessionState> = session.lock();
+        if state.invalidated {
+            if let Some(id) = state.id.take() {
+                if let Err(e) = self.store.destroy(&id) {
+                    log::error!("Failed to destroy session: {}", e);
+                }
+                res.set_cookie(self.session_cookie(None));
+            }
+            return;
+        }
+        if state.id.is_none() && state.data.is_empty() {
+            return;
+        }
+        if let (Some(id), false, false) = (&state.id, state.modified, state.regenerate) {
+            self.extend(req, res, id);
+            return;
+        }
+        if state.regenerate {
+            if let Some(old_id) = state.id.take() {
+                if let Err(e) = self.store.destroy(&old_id) {
+                    log::error!("Failed to destroy session: {}", e);
+                }
+            }
+        }
+        let id: String = state.id.get_or_insert_with(new_session_id).clone();
+        match self.store.save(&id, &state.data, self.max_age) {
+            Ok(()) => {
+                res.set_cookie(self.session_cookie(Some(&id)));
+            },
+            Err(e) => log::error!("Failed to save session: {}", e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::middleware::session_middleware::Sessions;
+    use crate::web::models::cookie::SameSite;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::sessions::memory_session_store::MemorySessionStore;
+    use crate::web::sessions::session::SessionData;
+    use crate::web::sessions::session_store::SessionStore;
+    use crate::web::util::encoders::hmac_signer;
+
+    /// A store counting its saves and touches.
+    #[derive(Default)]
+    struct CountingStore {
+        inner: MemorySessionStore,
+        saves: Arc<AtomicUsize>,
+        touches: Arc<AtomicUsize>
+    }
+
+    impl SessionStore for CountingStore {
+        fn load(&self, id: &str) -> Option<SessionData> {
+            self.inner.load(id)
+        }
+        fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> io::Result<()> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            self.inner.save(id, data, ttl)
+        }
+        fn touch(&self, id: &str, ttl: Duration) -> io::Result<()> {
+            self.touches.fetch_add(1, Ordering::SeqCst);
+            self.inner.touch(id, ttl)
+        }
+        fn destroy(&self, id: &str) -> io::Result<()> {
+            self.inner.destroy(id)
+        }
+    }
+
+    fn round_trip(sessions: &Sessions, cookie: Option<&str>, handler: fn(&Request)) -> Option<String> {
+        let container: IocContainer = IocContainer::default();
+        let head: String = match cookie {
+            Some(cookie) => format!("GET / HTTP/1.1\nCookie: theme=dark; session_id={}", cookie),
+            None => "GET / HTTP/1.1\nHost: localhost".to_string()
+        };
+        let mut req: Request = Request::new_without_stream(head.as_bytes(), b"").unwrap();
+        assert!(sessions.before(&mut req, &container).is_none());
+        handler(&req);
+        let mut res: Response = Response::ok();
+        sessions.after(&req, &mut res, &container);
+        res.cookies().first().map(|cookie| cookie.to_string())
+    }
+
+    fn cookie_value(set_cookie: &str) -> String {
+        set_cookie.split(';').next().unwrap().trim_start_matches("session_id=").to_string()
+    }
+
+    #[test]
+    fn keeps_data_across_requests() {
+        let sessions: Sessions = Sessions::new(MemorySessionStore::new(), b"secret")
+            .secure(false)
+            .same_site(SameSite::Strict)
+            .max_age(Duration::from_secs(60));
+        assert_eq!(round_trip(&sessions, None, |_| {}), None);
+
+        let set_cookie: String = round_trip(&sessions, None, |req: &Request| {
+            req.session().unwrap().insert("user", "admin");
+        }).unwrap();
+        assert!(set_cookie.ends_with("; Max-Age=60; Path=/; SameSite=Strict; HttpOnly"));
+        let cookie: String = cookie_value(&set_cookie);
+
+        round_trip(&sessions, Some(&cookie), |req: &Request| {
+            assert_eq!(req.session().unwrap().get("user").as_deref(), Some("admin"));
+        });
+        round_trip(&sessions, Some(&cookie.replace('.', "x.")), |req: &Request| {
+            assert!(req.session().unwrap().is_empty());
+        });
+
+        let removal: String = round_trip(&sessions, Some(&cookie), |req: &Request| {
+            req.session().unwrap().invalidate();
+        }).unwrap();
+        assert!(removal.starts_with("session_id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/"));
+        round_trip(&sessions, Some(&cookie), |req: &Request| {
+            assert!(req.session().unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn regenerates_id() {
+        let sessions: Sessions = Sessions::new(MemorySessionStore::new(), b"secret");
+        let cookie: String = cookie_value(&round_trip(&sessions, None, |req: &Request| {
+            req.session().unwrap().insert("cart", "3");
+        }).unwrap());
+        let regenerated: String = cookie_value(&round_trip(&sessions, Some(&cookie), |req: &Request| {
+            req.session().unwrap().regenerate();
+        }).unwrap());
+        assert_ne!(cookie, regenerated);
+        round_trip(&sessions, Some(&cookie), |req: &Request| {
+            assert!(req.session().unwrap().is_empty());
+        });
+        round_trip(&sessions, Some(&regenerated), |req: &Request| {
+            assert_eq!(req.session().unwrap().get("cart").as_deref(), Some("3"));
+        });
+    }
+
+    #[test]
+    fn saves_only_modified_sessions() {
+        let store: CountingStore = CountingStore::default();
+        let (saves, touches) = (Arc::clone(&store.saves), Arc::clone(&store.touches));
+        let sessions: Sessions = Sessions::new(store, b"secret");
+        let cookie: String = cookie_value(&round_trip(&sessions, None, |req: &Request| {
+            req.session().unwrap().insert("user", "admin");
+        }).unwrap());
+        assert_eq!(saves.load(Ordering::SeqCst), 1);
+
+        let set_cookie: Option<String> = round_trip(&sessions, Some(&cookie), |req: &Request| {
+            assert_eq!(req.session().unwrap().get("user").as_deref(), Some("admin"));
+        });
+        assert_eq!(set_cookie, None);
+        assert_eq!((saves.load(Ordering::SeqCst), touches.load(Ordering::SeqCst)), (1, 0));
+
+        // A cookie issued long ago is refreshed, and the session extended without saving it.
+        let id: &str = cookie.split('.').next().unwrap();
+        let old_cookie: String = hmac_signer::sign(b"secret", &format!("{}.0", id));
+        let refreshed: String = cookie_value(&round_trip(&sessions, Some(&old_cookie), |_| {}).unwrap());
+        assert!(refreshed.starts_with(id));
+        assert_eq!((saves.load(Ordering::SeqCst), touches.load(Ordering::SeqCst)), (1, 1));
+
+        round_trip(&sessions, Some(&cookie), |req: &Request| {
+            req.session().unwrap().insert("theme", "dark");
+        }).unwrap();
+        assert_eq!(saves.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{Cursor, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use crate::web::util::parsers::range_parser::ByteRange;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The chunks of a `Body::Chunks`. An error ends the response early.
+pub type ChunkIterator = Box<dyn Iterator<Item = io::Result<Vec<u8>>> + Send>;
+
+/// `Body` is the body of a response. It's either in memory, or streamed to the client while it's
+/// being sent, so large bodies, e.g. exports, never have to fit in memory.
+///
+/// Streamed bodies with a known length are sent with `Content-Length`, the others with
+/// `Transfer-Encoding: chunked`.
+pub enum Body {
+    /// A body in memory.
+    Bytes(Vec<u8>),
+    /// A body read from a reader while it's sent, with its length if it's known.
+    Reader(Box<dyn Read + Send>, Option<u64>),
+    /// A body produced chunk by chunk while it's sent. Empty chunks are skipped.
+    Chunks(ChunkIterator),
+    /// A file, sent from its current position, with the number of bytes to send. On Linux, the
+    /// standard library copies it to the connection with `sendfile`, without going through memory.
+    File(File, u64)
+}
+
+impl Body {
+    /// It streams a body from any reader, e.g. a pipe or a database export.
+    ///
+    /// Arguments:
+    ///
+    /// * `reader`: Where the body is read from.
+    /// * `length`: How many bytes the reader will give, or `None` to send it chunked.
+    pub fn from_reader<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> Self {
+        Body::Reader(Box::new(reader), length)
+    }
+
+    /// It streams a body made of chunks, e.g. one per row of a report. It's always sent chunked.
+    pub fn from_chunks<I: Iterator<Item = io::Result<Vec<u8>>> + Send + 'static>(chunks: I) -> Self {
+        Body::Chunks(Box::new(chunks))
+    }
+
+    /// It streams a whole file, with its length from its metadata.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path of the file.
+    ///
+    /// Returns:
+    ///
+    /// The Body, or the error of opening the file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file: File = File::open(path)?;
+        let length: u64 = file.metadata()?.len();
+        Ok(Body::File(file, length))
+    }
+
+    /// It streams several ranges of a file as a `multipart/byteranges` body, for a `206 Partial
+    /// Content` response. Every part has its own `Content-Type` and `Content-Range` headers.
+    ///
+    /// Arguments:
+    ///
+    /// * `file`: The file.
+    /// * `file_length`: The length of the whole file.
+    /// * `ranges`: The ranges to send, in order.
+    /// * `content_type`: The type of the file, repeated in every part.
+    /// * `boundary`: The boundary between the parts, which has to be in the `Content-Type` of the
+    ///   response.
+    pub fn from_file_ranges(file: File, file_length: u64, ranges: &[ByteRange], content_type: &str, boundary: &str) -> Self {
+        let mut parts: VecDeque<RangePart> = VecDeque::with_capacity(ranges.len() * 2 + 1);
+        let mut length: u64 = 0;
+        for (i, range) in ranges.iter().enumerate() {
+            let head: String = format!("{}--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                                       if i == 0 { "" } else { "\r\n" }, boundary, content_type,
+                                       range.start(), range.end(), file_length);
+            length += head.len() as u64 + range.length();
+            parts.push_back(RangePart::Text(Cursor::new(head.into_bytes())));
+            parts.push_back(RangePart::Section { start: range.start(), remaining: range.length(), positioned: false });
+        }
+        let tail: String = format!("\r\n--{}--\r\n", boundary);
+        length += tail.len() as u64;
+        parts.push_back(RangePart::Text(Cursor::new(tail.into_bytes())));
+        Body::Reader(Box::new(MultipartRanges { file, parts }), Some(length))
+    }
+
+    /// How long the body is, or `None` if it's only known once it's sent.
+    pub fn length(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::Reader(_, length) => *length,
+            Body::Chunks(_) => None,
+            Body::File(_, length) => Some(*length)
+        }
+    }
+
+    /// It returns `true` if the body is read while it's sent, rather than held in memory.
+    pub fn is_streaming(&self) -> bool {
+        !matches!(self, Body::Bytes(_))
+    }
+}
+
+impl Default for Body {
+    fn default() -> Self {
+        Body::Bytes(Vec::new())
+    }
+}
+
+impl Debug for Body {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Body::Bytes(bytes) => write!(f, "{:?}", String::from_utf8_lossy(bytes)),
+            Body::Reader(_, Some(length)) => write!(f, "<stream of {} bytes>", length),
+            Body::File(_, length) => write!(f, "<file of {} bytes>", length),
+            Body::Reader(_, None) | Body::Chunks(_) => write!(f, "<chunked stream>")
+        }
+    }
+}
+
+/// A part of a `multipart/byteranges` body: the boundary and headers, or a section of the file.
+enum RangePart {
+    Text(Cursor<Vec<u8>>),
+    Section { start: u64, remaining: u64, positioned: bool }
+}
+
+/// `MultipartRanges` reads the parts of a `multipart/byteranges` body one after the other, seeking
+/// to every section of the file when it gets to it.
+struct MultipartRanges {
+    file: File,
+    parts: VecDeque<RangePart>
+}
+
+impl Read for MultipartRanges {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while let Some(part) = self.parts.front_mut() {
+            let read: usize = match part {
+                RangePart::Text(text) => text.read(buf)?,
+                RangePart::Section { remaining: 0, .. } => 0,
+                RangePart::Section { start, remaining, positioned } => {
+                    if !*positioned {
+                        self.file.seek(SeekFrom::Start(*start))?;
+                        *positioned = true;
+                    }
+                    let max: usize = (*remaining).min(buf.len() as u64) as usize;
+                    let read: usize = self.file.read(&mut buf[..max])?;
+                    if read == 0 {
+                        return Err(io::Error::new(ErrorKind::UnexpectedEof, "File is shorter than the range"));
+                    }
+                    *remaining -= read as u64;
+                    read
+                }
+            };
+            if read > 0 {
+                return Ok(read);
+            }
+            self.parts.pop_front();
+        }
+        Ok(0)
+    }
+}
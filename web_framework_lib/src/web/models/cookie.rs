@@ -0,0 +1,204 @@
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::web::util::parsers::http_date_parser::format_http_date;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The `SameSite` attribute of a cookie, controlling if it is sent with cross-site requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None"
+        }
+    }
+}
+
+/// `Cookie` is a cookie sent to the client with `res.set_cookie`, formatted as a `Set-Cookie`
+/// header. The value is sent as is, so it must only contain characters allowed by RFC 6265, e.g.
+/// url or base64url encoded data.
+///
+/// Properties:
+///
+/// * `name`: The name of the cookie.
+/// * `value`: The value of the cookie.
+/// * `expires`: The `Expires` attribute, when the cookie expires.
+/// * `max_age`: The `Max-Age` attribute, how long the cookie lives, preferred over `expires`.
+/// * `domain`: The `Domain` attribute, the hosts the cookie is sent to.
+/// * `path`: The `Path` attribute, the paths the cookie is sent to.
+/// * `secure`: The `Secure` attribute, so the cookie is only sent over HTTPS.
+/// * `http_only`: The `HttpOnly` attribute, so scripts can't read the cookie.
+/// * `same_site`: The `SameSite` attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    expires: Option<SystemTime>,
+    max_age: Option<Duration>,
+    domain: Option<String>,
+    path: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>
+}
+
+impl Cookie {
+    /// `new` creates a session cookie without attributes.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the cookie.
+    /// * `value`: The value of the cookie.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Cookie struct.
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            expires: None,
+            max_age: None,
+            domain: None,
+            path: None,
+            secure: false,
+            http_only: false,
+            same_site: None
+        }
+    }
+
+    /// `removal` creates a cookie telling the client to delete the cookie with this name. Its
+    /// `path` and `domain` must match the ones the cookie was set with.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the cookie to delete.
+    ///
+    /// Returns:
+    ///
+    /// A cookie with an empty value, that has already expired.
+    pub fn removal(name: &str) -> Self {
+        Self::new(name, "")
+            .max_age(Duration::ZERO)
+            .expires(UNIX_EPOCH)
+    }
+
+    pub fn value(mut self, value: &str) -> Self {
+        self.value = value.to_string();
+        self
+    }
+    pub fn expires(mut self, expires: SystemTime) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+    pub fn http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+    pub fn get_domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+    pub fn get_path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// It checks if two cookies are stored in the same slot by the client, which is the case if
+    /// their name, domain and path are equal.
+    pub fn same_slot(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+impl Display for Cookie {
+    /// It formats the cookie as the value of a `Set-Cookie` header.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(expires) = self.expires {
+            write!(f, "; Expires={}", format_http_date(expires))?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use crate::web::models::cookie::{Cookie, SameSite};
+
+    #[test]
+    fn formats_set_cookie() {
+        let cookie: Cookie = Cookie::new("theme", "dark")
+            .max_age(Duration::from_secs(3600))
+            .domain("example.com")
+            .path("/")
+            .same_site(SameSite::Lax)
+            .secure(true)
+            .http_only(true);
+        assert_eq!(cookie.to_string(),
+                   "theme=dark; Max-Age=3600; Domain=example.com; Path=/; SameSite=Lax; Secure; HttpOnly");
+        assert_eq!(Cookie::removal("theme").path("/").to_string(),
+                   "theme=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/");
+    }
+}
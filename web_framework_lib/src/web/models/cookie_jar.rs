@@ -0,0 +1,160 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use chacha20poly1305::aead::{Aead, Payload};
+use di_ioc_lib::di::providable_trait::Providable;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use crate::web::models::cookie::Cookie;
+use crate::web::models::request::Request;
+use crate::web::util::encoders::hmac_signer;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+const NONCE_LENGTH: usize = 12;
+
+/// `CookieJar` protects cookie values with keys derived from a server secret. Signed cookies can be
+/// read by the client but not changed, encrypted cookies can neither be read nor changed. The name
+/// of a cookie is part of the protection, so a value can't be moved to another cookie.
+///
+/// Install it in the `IocContainer` so handlers and middleware share the keys.
+///
+/// Properties:
+///
+/// * `signing_key`: The HMAC-SHA256 key for signed cookies.
+/// * `encryption_key`: The ChaCha20-Poly1305 key for encrypted cookies.
+pub struct CookieJar {
+    signing_key: [u8; 32],
+    encryption_key: [u8; 32]
+}
+
+impl Providable for CookieJar { }
+
+/// It derives a key for one purpose from the server secret, so signatures made for one purpose
+/// are never valid for another.
+pub(crate) fn derive_key(secret: &[u8], purpose: &str) -> [u8; 32] {
+    let mut mac: Hmac<Sha256> = <Hmac<Sha256> as Mac>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(purpose.as_bytes());
+    mac.finalize().into_bytes().into()
+}
+
+impl CookieJar {
+    /// `new` creates a jar with keys derived from the server secret.
+    ///
+    /// Arguments:
+    ///
+    /// * `secret`: The server secret, at least 32 random bytes.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the CookieJar struct.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            signing_key: derive_key(secret, "cookie-signing"),
+            encryption_key: derive_key(secret, "cookie-encryption")
+        }
+    }
+
+    /// It signs the value of a cookie. The value must only contain characters allowed in cookies.
+    pub fn signed(&self, cookie: Cookie) -> Cookie {
+        let name_and_value: String = format!("{}={}", cookie.get_name(), cookie.get_value());
+        let signed: String = hmac_signer::sign(&self.signing_key, &name_and_value);
+        let value: String = signed[cookie.get_name().len() + 1..].to_string();
+        cookie.value(&value)
+    }
+
+    /// It reads a signed cookie of the request.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request.
+    /// * `name`: The name of the cookie.
+    ///
+    /// Returns:
+    ///
+    /// The value of the cookie, or `None` if it is missing or its signature is wrong.
+    pub fn get_signed(&self, req: &Request, name: &str) -> Option<String> {
+        let name_and_value: String = format!("{}={}", name, req.cookie(name)?);
+        hmac_signer::verify(&self.signing_key, &name_and_value)
+            .map(|verified: &str| verified[name.len() + 1..].to_string())
+    }
+
+    /// It encrypts the value of a cookie. The value can contain any characters.
+    pub fn encrypted(&self, cookie: Cookie) -> Cookie {
+        let cipher: ChaCha20Poly1305 = ChaCha20Poly1305::new(Key::from_slice(&self.encryption_key));
+        let mut nonce: [u8; NONCE_LENGTH] = [0; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+        let payload: Payload = Payload { msg: cookie.get_value().as_bytes(), aad: cookie.get_name().as_bytes() };
+        let mut sealed: Vec<u8> = nonce.to_vec();
+        sealed.extend(cipher.encrypt(Nonce::from_slice(&nonce), payload).expect("Encryption failed."));
+        cookie.value(&URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    /// It reads an encrypted cookie of the request.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request.
+    /// * `name`: The name of the cookie.
+    ///
+    /// Returns:
+    ///
+    /// The decrypted value, or `None` if the cookie is missing or was tampered with.
+    pub fn get_encrypted(&self, req: &Request, name: &str) -> Option<String> {
+        let sealed: Vec<u8> = URL_SAFE_NO_PAD.decode(req.cookie(name)?).ok()?;
+        if sealed.len() < NONCE_LENGTH {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let cipher: ChaCha20Poly1305 = ChaCha20Poly1305::new(Key::from_slice(&self.encryption_key));
+        let payload: Payload = Payload { msg: ciphertext, aad: name.as_bytes() };
+        let plaintext: Vec<u8> = cipher.decrypt(Nonce::from_slice(nonce), payload).ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::models::cookie::Cookie;
+    use crate::web::models::cookie_jar::CookieJar;
+    use crate::web::models::request::Request;
+
+    fn request_with_cookies(cookies: &[&Cookie]) -> Request {
+        let header: Vec<String> = cookies.iter()
+            .map(|cookie: &&Cookie| format!("{}={}", cookie.get_name(), cookie.get_value()))
+            .collect();
+        let head: String = format!("GET / HTTP/1.1\nCookie: {}", header.join("; "));
+        Request::new_without_stream(head.as_bytes(), b"").unwrap()
+    }
+
+    #[test]
+    fn signs_and_encrypts() {
+        let jar: CookieJar = CookieJar::new(b"server secret");
+        let signed: Cookie = jar.signed(Cookie::new("user", "admin"));
+        let encrypted: Cookie = jar.encrypted(Cookie::new("cart", "3 items; cheap"));
+        assert!(signed.get_value().starts_with("admin."));
+        assert!(!encrypted.get_value().contains("items"));
+
+        let req: Request = request_with_cookies(&[&signed, &encrypted]);
+        assert_eq!(jar.get_signed(&req, "user").as_deref(), Some("admin"));
+        assert_eq!(jar.get_encrypted(&req, "cart").as_deref(), Some("3 items; cheap"));
+        assert_eq!(CookieJar::new(b"other secret").get_signed(&req, "user"), None);
+
+        let forged: Cookie = Cookie::new("user", &signed.get_value().replace("admin", "root"));
+        let moved: Cookie = Cookie::new("wishlist", encrypted.get_value());
+        let req: Request = request_with_cookies(&[&forged, &moved]);
+        assert_eq!(jar.get_signed(&req, "user"), None);
+        assert_eq!(jar.get_encrypted(&req, "wishlist"), None);
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt::{Display, Formatter};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `ETag` is an entity tag, identifying a version of a resource. Strong tags change with every
+/// byte, weak ones only with the meaning, e.g. not when the body is compressed.
+///
+/// Properties:
+///
+/// * `tag`: The opaque tag, without quotes.
+/// * `weak`: If the tag is weak, sent with a `W/` prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag {
+    tag: String,
+    weak: bool
+}
+
+impl ETag {
+    /// `strong` creates a strong tag. Quotes are removed from the tag.
+    pub fn strong(tag: &str) -> Self {
+        Self { tag: tag.replace('"', ""), weak: false }
+    }
+    /// `weak` creates a weak tag. Quotes are removed from the tag.
+    pub fn weak(tag: &str) -> Self {
+        Self { tag: tag.replace('"', ""), weak: true }
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// It turns the tag into a weak one, e.g. when the body is transformed.
+    pub fn into_weak(self) -> Self {
+        Self { weak: true, ..self }
+    }
+
+    /// Strong comparison, used by `If-Match`: both tags must be strong and equal.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, used by `If-None-Match`: the tags must be equal, weak or not.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+
+    /// It parses a single entity tag, e.g. `"abc"` or `W/"abc"`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value: &str = value.trim();
+        let (weak, quoted) = match value.strip_prefix("W/") {
+            Some(quoted) => (true, quoted),
+            None => (false, value)
+        };
+        let tag: &str = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        if tag.contains('"') {
+            return None;
+        }
+        Some(Self { tag: tag.to_string(), weak })
+    }
+
+    /// It parses the value of an `If-Match` or `If-None-Match` header.
+    ///
+    /// Arguments:
+    ///
+    /// * `value`: The header value, e.g. `"a", W/"b"`.
+    ///
+    /// Returns:
+    ///
+    /// `None` for `*`, which matches any tag, otherwise the tags. Malformed tags are skipped.
+    pub fn parse_list(value: &str) -> Option<Vec<ETag>> {
+        if value.trim() == "*" {
+            return None;
+        }
+        let mut tags: Vec<ETag> = Vec::new();
+        let mut rest: &str = value;
+        while let Some(start) = rest.find('"') {
+            let end: usize = match rest[start + 1..].find('"') {
+                Some(end) => start + 1 + end,
+                None => break
+            };
+            let weak: bool = rest[..start].trim_end().ends_with("W/");
+            tags.push(Self { tag: rest[start + 1..end].to_string(), weak });
+            rest = &rest[end + 1..];
+        }
+        Some(tags)
+    }
+}
+
+impl Display for ETag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.weak {
+            write!(f, "W/\"{}\"", self.tag)
+        } else {
+            write!(f, "\"{}\"", self.tag)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::models::etag::ETag;
+
+    #[test]
+    fn parses_and_compares() {
+        assert_eq!(ETag::parse("W/\"abc\""), Some(ETag::weak("abc")));
+        assert_eq!(ETag::parse("abc"), None);
+        assert_eq!(ETag::parse_list("*"), None);
+        assert_eq!(ETag::parse_list("\"a,b\", W/\"c\""), Some(vec![ETag::strong("a,b"), ETag::weak("c")]));
+        assert!(ETag::strong("a").weak_eq(&ETag::weak("a")));
+        assert!(!ETag::strong("a").strong_eq(&ETag::weak("a")));
+        assert_eq!(ETag::strong("a").into_weak().to_string(), "W/\"a\"");
+    }
+}
@@ -0,0 +1,144 @@
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `HeaderMap` holds the headers of a request or a response. Names are case-insensitive, so
+/// `content-type` finds `Content-Type`, but keep the case they were added with. Headers stay in the
+/// order they were added, and a name can have several values, e.g. `Link` or `Via`, which are
+/// sent as separate header lines.
+///
+/// Properties:
+///
+/// * `entries`: The names and values, in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// It sets a header, replacing all its values. The header keeps its position if it was
+    /// already set.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the header.
+    /// * `value`: The new value.
+    ///
+    /// Returns:
+    ///
+    /// The first value it had before, if any.
+    pub fn insert(&mut self, name: &str, value: String) -> Option<String> {
+        let position: Option<usize> = self.position(name);
+        match position {
+            Some(position) => {
+                let previous: String = std::mem::replace(&mut self.entries[position].1, value);
+                let mut index: usize = 0;
+                self.entries.retain(|(current, _)| {
+                    index += 1;
+                    index - 1 <= position || !current.eq_ignore_ascii_case(name)
+                });
+                Some(previous)
+            },
+            None => {
+                self.entries.push((name.to_string(), value));
+                None
+            }
+        }
+    }
+
+    /// It adds a value to a header, keeping the values it already has.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the header.
+    /// * `value`: The value to add.
+    pub fn append(&mut self, name: &str, value: String) {
+        self.entries.push((name.to_string(), value));
+    }
+
+    /// It removes every value of a header.
+    ///
+    /// Returns:
+    ///
+    /// The first removed value, if the header was set.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let position: usize = self.position(name)?;
+        let removed: String = self.entries.remove(position).1;
+        self.entries.retain(|(current, _)| !current.eq_ignore_ascii_case(name));
+        Some(removed)
+    }
+
+    /// It returns the first value of a header.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name).map(|position: usize| self.entries[position].1.as_str())
+    }
+
+    /// It returns every value of a header, in order.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries.iter()
+            .filter(|(current, _)| current.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// It iterates over the headers as names and values, in order. Headers with several values
+    /// appear once per value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// How many header lines there are, counting every value.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|(current, _)| current.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::models::header_map::HeaderMap;
+
+    #[test]
+    fn keeps_order_and_values() {
+        let mut headers: HeaderMap = HeaderMap::new();
+        headers.insert("Content-Type", "text/html".to_string());
+        headers.append("Link", "</a.css>; rel=preload".to_string());
+        headers.insert("X-Id", "1".to_string());
+        headers.append("link", "</b.js>; rel=preload".to_string());
+
+        assert_eq!(headers.get("content-type"), Some("text/html"));
+        assert_eq!(headers.get_all("LINK"), vec!["</a.css>; rel=preload", "</b.js>; rel=preload"]);
+        assert_eq!(headers.len(), 4);
+
+        assert_eq!(headers.insert("LINK", "</c.js>".to_string()), Some("</a.css>; rel=preload".to_string()));
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Content-Type", "Link", "X-Id"]);
+        assert_eq!(headers.get("Link"), Some("</c.js>"));
+
+        assert_eq!(headers.remove("x-id"), Some("1".to_string()));
+        assert!(!headers.contains_key("X-Id"));
+        assert_eq!(headers.remove("X-Id"), None);
+    }
+}
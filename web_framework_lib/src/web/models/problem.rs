@@ -0,0 +1,187 @@
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::util::parsers::json_parser::{escape_json_object, escape_json_string, JsonObject, JsonVariant};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+
+/// The media type of problem details.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+/// The members defined by RFC 7807, which extensions can't replace.
+const STANDARD_MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+/// `Problem` describes an error of an API in the format of RFC 7807, so clients can handle errors
+/// of every endpoint the same way. Handlers return it with `into()`:
+/// ```
+/// use web_framework_lib::web::models::problem::Problem;
+/// use web_framework_lib::web::models::response::Response;
+/// use web_framework_lib::web::models::status_code::StatusCode;
+/// use web_framework_lib::web::util::parsers::json_parser::JsonVariant;
+/// let res: Response = Problem::new(StatusCode::CONFLICT)
+///     .problem_type("https://example.com/problems/out-of-credit")
+///     .title("You do not have enough credit.")
+///     .detail("Your current balance is 30, but that costs 50.")
+///     .instance("/account/12345/msgs/abc")
+///     .extension("balance", JsonVariant::from(30.0))
+///     .into();
+/// assert_eq!(res.status(), 409);
+/// ```
+///
+/// Properties:
+///
+/// * `problem_type`: A URI identifying the kind of problem, "about:blank" if it's only the status.
+/// * `title`: A short summary of the kind of problem, the reason phrase of the status by default.
+/// * `status`: The HTTP status code.
+/// * `detail`: What went wrong this time, for the client.
+/// * `instance`: A URI identifying this occurrence of the problem.
+/// * `extensions`: More members, e.g. the fields that failed validation.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    problem_type: String,
+    title: String,
+    status: StatusCode,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: JsonObject
+}
+
+impl Problem {
+    /// `new` creates a problem of the type "about:blank", titled with the reason phrase of the
+    /// status.
+    ///
+    /// Arguments:
+    ///
+    /// * `status`: The HTTP status code.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Problem struct.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.reason_phrase().to_string(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: JsonObject::new()
+        }
+    }
+
+    pub fn problem_type(mut self, problem_type: &str) -> Self {
+        self.problem_type = problem_type.to_string();
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    pub fn instance(mut self, instance: &str) -> Self {
+        self.instance = Some(instance.to_string());
+        self
+    }
+
+    /// It adds a member, e.g. `balance`. The members of RFC 7807 are ignored, set them with their
+    /// own functions. Strings are escaped when the problem is serialized, so pass them unescaped.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the member.
+    /// * `value`: The value of the member.
+    pub fn extension(mut self, name: &str, value: JsonVariant) -> Self {
+        if !STANDARD_MEMBERS.contains(&name) {
+            self.extensions.insert(name, value);
+        }
+        self
+    }
+
+    pub fn get_problem_type(&self) -> &str {
+        &self.problem_type
+    }
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+    pub fn get_status(&self) -> StatusCode {
+        self.status
+    }
+    pub fn get_detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+    pub fn get_instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+    pub fn get_extensions(&self) -> &JsonObject {
+        &self.extensions
+    }
+
+    /// It builds the JSON object of the problem, with the extensions and the members of RFC 7807.
+    /// Text members and extensions are escaped.
+    pub fn to_json(&self) -> JsonObject {
+        let mut json: JsonObject = escape_json_object(&self.extensions);
+        let text = |text: &str| JsonVariant::JsonString(escape_json_string(text));
+        json.insert("type", text(&self.problem_type))
+            .insert("title", text(&self.title))
+            .insert("status", JsonVariant::JsonNumber(f64::from(self.status.as_u16())));
+        if let Some(detail) = &self.detail {
+            json.insert("detail", text(detail));
+        }
+        if let Some(instance) = &self.instance {
+            json.insert("instance", text(instance));
+        }
+        json
+    }
+}
+
+impl From<Problem> for Response {
+    /// It answers with the problem as `application/problem+json`, with its status.
+    fn from(problem: Problem) -> Self {
+        let mut res: Response = Response::new(problem.status);
+        res.set_body(problem.to_json().to_string());
+        res.add_header("Content-Type", PROBLEM_JSON.to_string());
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::models::problem::Problem;
+    use crate::web::models::response::Response;
+    use crate::web::models::status_code::StatusCode;
+    use crate::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+
+    #[test]
+    fn serializes_problem_details() {
+        let problem: Problem = Problem::new(StatusCode::UNPROCESSABLE_CONTENT)
+            .detail("Field \"email\" is invalid")
+            .extension("status", JsonVariant::from("ignored"))
+            .extension("fields", JsonVariant::JsonArray(vec![JsonVariant::from("email")]))
+            .extension("hint", JsonVariant::JsonArray(vec![JsonVariant::from(r#"use "a\b""#)]));
+        let json: JsonObject = problem.to_json();
+        assert_eq!(json.get("type").unwrap().to_string(), r#""about:blank""#);
+        assert_eq!(json.get("title").unwrap().to_string(), r#""Unprocessable Content""#);
+        assert_eq!(json.get("status").unwrap().to_string(), "422");
+        assert_eq!(json.get("detail").unwrap().to_string(), r#""Field \"email\" is invalid""#);
+        assert_eq!(json.get("fields").unwrap().to_string(), r#"["email"]"#);
+        assert_eq!(json.get("hint").unwrap().to_string(), r#"["use \"a\\b\""]"#);
+        assert!(json.get("instance").is_none());
+
+        let res: Response = problem.into();
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_CONTENT);
+        assert_eq!(res.headers().get("Content-Type"), Some("application/problem+json"));
+    }
+}
@@ -1,18 +1,24 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::io::Write;
-use std::net::TcpStream;
+use std::any::Any;
+use std::net::{SocketAddr, TcpStream};
 use std::sync::{LockResult, Mutex, MutexGuard};
 
-use crate::web::models::request::request_headers::RequestHeaders;
+use crate::web::middleware::access_log_middleware::RequestId;
+use crate::web::middleware::csrf_middleware::CsrfToken;
+use crate::web::middleware::security_headers_middleware::CspNonce;
+use crate::web::models::header_map::HeaderMap;
+use crate::web::models::request::request_extensions::RequestExtensions;
 use crate::web::models::request::request_line_data::RequestLineData;
 use crate::web::models::response::Response;
 use crate::web::request_handling::request_handler::HandleError;
+use crate::web::request_handling::route::MatchedRoute;
+use crate::web::sessions::session::Session;
+use crate::web::util::parsers::cookie_parser::parse_cookie_header;
 use crate::web::util::parsers::json_parser::{JsonObject, JsonParseError, parse_into_json_object};
 use crate::web::util::parsers::request_parser::RequestParseError;
 
 mod request_line_data;
-mod request_headers;
+mod request_extensions;
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
 // ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
@@ -31,14 +37,17 @@ mod request_headers;
 /// Properties:
 ///
 /// * `request_line_data`: This is a struct that contains the request line data.
-/// * `request_header_map`: This is a HashMap that will contain the request headers.
-/// * `stream`: The stream of data that the request is coming in on.
+/// * `headers`: The request headers.
+/// * `stream`: The stream of data that the request is coming in on, `None` for requests that
+///   were built without a connection, e.g. in tests.
+/// * `extensions`: Values attached to the request by middleware.
 #[derive(Debug)]
 pub struct Request {
     request_line_data: RequestLineData,
-    request_headers: Option<RequestHeaders>,
+    headers: HeaderMap,
     body: Vec<u8>,
-    stream: TcpStream,
+    stream: Option<TcpStream>,
+    extensions: RequestExtensions,
     resolved: Mutex<bool>
 }
 
@@ -55,16 +64,34 @@ impl Request {
     ///
     /// A new instance of the Request struct.
     pub fn new(req_line_data_and_headers: &[u8], body: &[u8], stream: TcpStream) -> Result<Self, RequestParseError> {
+        let mut req: Request = Self::new_without_stream(req_line_data_and_headers, body)?;
+        req.set_stream(stream);
+        Ok(req)
+    }
+
+    /// It parses a request that isn't tied to a connection. Resolving it will fail, but it can be
+    /// passed through the middleware chain and to handlers, which makes it useful for testing.
+    ///
+    /// Arguments:
+    ///
+    /// * `req_line_data_and_headers`: The request line and the headers, separated by newlines.
+    /// * `body`: The body of the request.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Request struct.
+    pub fn new_without_stream(req_line_data_and_headers: &[u8], body: &[u8]) -> Result<Self, RequestParseError> {
         let lossy_utf8: Cow<str> = String::from_utf8_lossy(req_line_data_and_headers);
         let mut req_split_new_line: Vec<&str> = lossy_utf8.lines().collect();
         req_split_new_line.reverse();
-        let request_line_data: RequestLineData = RequestLineData::new(req_split_new_line.pop().expect("No first line"))?;
-        let request_headers: Option<RequestHeaders> = RequestHeaders::new(Self::req_str_to_header_map(req_split_new_line.to_owned()));
+        let request_line_data: RequestLineData = RequestLineData::new(req_split_new_line.pop().ok_or(RequestParseError::NoMethod)?)?;
+        let headers: HeaderMap = Self::req_str_to_header_map(req_split_new_line.to_owned());
         Ok(Self {
             request_line_data,
-            request_headers,
+            headers,
             body: Vec::from(body),
-            stream,
+            stream: None,
+            extensions: RequestExtensions::default(),
             resolved: Mutex::new(false)
         })
     }
@@ -80,17 +107,31 @@ impl Request {
          }
      }
 
+    /// It writes the response to the connection, without the body for `HEAD` requests. A request
+    /// can only be resolved once.
+    ///
+    /// Arguments:
+    ///
+    /// * `res`: The response to send.
+    ///
+    /// Returns:
+    ///
+    /// `HandleError::InvalidHeader` if the response has a header that can't be sent, in which case
+    /// nothing was written and the request can still be resolved with another response.
     pub fn resolve(&self, mut res: Response) -> Result<(), HandleError> {
         let mutex_lock: LockResult<MutexGuard<bool>> = self.resolved.lock();
         if let Ok(mut t) = mutex_lock {
             return if *t {
                 Err(HandleError::AlreadyResolved)
-            } else if res.status() == 0 {
-                Err(HandleError::HttpStatusNotSet)
             } else {
-                let write_result: std::io::Result<usize> = self.stream()
-                    .write(res.get_as_u8_vec()
-                        .as_slice());
+                if let Some(name) = res.invalid_header() {
+                    return Err(HandleError::InvalidHeader(name.to_string()))
+                }
+                let write_result: std::io::Result<()> = match self.stream() {
+                    Some(mut stream) if self.request_line_data.method() == "HEAD" => res.write_head_to(&mut stream),
+                    Some(mut stream) => res.write_to(&mut stream),
+                    None => return Err(HandleError::ResolveFailed)
+                };
                 if write_result.is_err() {
                     return Err(HandleError::ResolveFailed)
                 }
@@ -110,13 +151,13 @@ impl Request {
     ///
     /// Returns:
     ///
-    /// A HashMap<String, String>
-    fn req_str_to_header_map(req_str: Vec<&str>) -> HashMap<String, String> {
-        let mut req_header_map: HashMap<String, String> = HashMap::new();
-        for x in req_str.into_iter() {
+    /// A HeaderMap, keeping repeated headers
+    fn req_str_to_header_map(req_str: Vec<&str>) -> HeaderMap {
+        let mut req_header_map: HeaderMap = HeaderMap::new();
+        for x in req_str.into_iter().rev() {
             match x.split_once(':') {
                 Some((hdr_key, hdr_val)) => {
-                    req_header_map.insert(hdr_key.trim().to_string(),
+                    req_header_map.append(hdr_key.trim(),
                                           hdr_val.trim().to_string());
                 },
                 None => {}
@@ -125,17 +166,94 @@ impl Request {
         req_header_map
     }
 
+    /// It looks up a request header by its case-insensitive name.
+    ///
+    /// Arguments:
+    ///
+    /// * `key`: The name of the header, e.g. "Accept".
+    ///
+    /// Returns:
+    ///
+    /// The value of the header, or `None` if the request has no such header.
+    pub fn get_header(&self, key: &str) -> Option<&str> {
+        self.headers.get(key)
+    }
+
+    /// It parses the `Cookie` header.
+    ///
+    /// Returns:
+    ///
+    /// The name and value of every cookie, in the order they were sent.
+    pub fn cookies(&self) -> Vec<(&str, &str)> {
+        self.get_header("Cookie")
+            .map(parse_cookie_header)
+            .unwrap_or_default()
+    }
+
+    /// It looks up a cookie by its case-sensitive name.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the cookie.
+    ///
+    /// Returns:
+    ///
+    /// The value of the first cookie with that name, or `None` if it wasn't sent.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies().into_iter()
+            .find(|(cookie_name, _)| *cookie_name == name)
+            .map(|(_, value)| value)
+    }
+
     pub fn request_line_data(&self) -> &RequestLineData {
         &self.request_line_data
     }
-    pub fn request_headers(&self) -> &Option<RequestHeaders> {
-        &self.request_headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
     }
     pub fn body(&self) -> &Vec<u8> {
         &self.body
     }
-    pub fn stream(&self) -> &TcpStream {
-        &self.stream
+    pub fn stream(&self) -> Option<&TcpStream> {
+        self.stream.as_ref()
+    }
+    /// The address of the client, if the request came in on a connection.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.as_ref()
+            .and_then(|stream: &TcpStream| stream.peer_addr().ok())
+    }
+    pub fn extensions(&self) -> &RequestExtensions {
+        &self.extensions
+    }
+    pub fn extensions_mut(&mut self) -> &mut RequestExtensions {
+        &mut self.extensions
+    }
+    /// Shorthand for `req.extensions().get::<T>()`.
+    pub fn extension<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.extensions.get::<T>()
+    }
+    /// The session of the request, if the `Sessions` middleware is installed.
+    pub fn session(&self) -> Option<&Session> {
+        self.extensions.get::<Session>()
+    }
+    /// The path pattern of the route the request matched, e.g. "/users/{id}", if it matched one.
+    pub fn route_pattern(&self) -> Option<&str> {
+        self.extensions.get::<MatchedRoute>().map(|route: &MatchedRoute| route.pattern())
+    }
+    /// The ID of the request, if the `AccessLog` middleware is installed.
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.extensions.get::<RequestId>()
+    }
+    /// The CSRF token of the client, if the `Csrf` middleware is installed.
+    pub fn csrf_token(&self) -> Option<&CsrfToken> {
+        self.extensions.get::<CsrfToken>()
+    }
+    /// The nonce of the `Content-Security-Policy`, if the `SecurityHeaders` middleware is installed.
+    pub fn csp_nonce(&self) -> Option<&CspNonce> {
+        self.extensions.get::<CspNonce>()
     }
     pub fn resolved(&self) -> &Mutex<bool> {
         &self.resolved
@@ -144,14 +262,14 @@ impl Request {
     pub fn set_request_line_data(&mut self, request_line_data: RequestLineData) {
         self.request_line_data = request_line_data;
     }
-    pub fn set_request_headers(&mut self, request_headers: Option<RequestHeaders>) {
-        self.request_headers = request_headers;
+    pub fn set_headers(&mut self, headers: HeaderMap) {
+        self.headers = headers;
     }
     pub fn set_body(&mut self, body: Vec<u8>) {
         self.body = body;
     }
     pub fn set_stream(&mut self, stream: TcpStream) {
-        self.stream = stream;
+        self.stream = Some(stream);
     }
     pub fn set_resolved(&mut self, resolved: Mutex<bool>) {
         self.resolved = resolved;
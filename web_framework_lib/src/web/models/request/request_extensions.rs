@@ -0,0 +1,67 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `RequestExtensions` is a map from a type to a single value of that type. Middleware uses it to
+/// attach data to a request, e.g. the start time of the request, or the authenticated user, for
+/// handlers and later middleware to read.
+///
+/// Properties:
+///
+/// * `map`: The values, keyed by the `TypeId` of their type.
+#[derive(Default)]
+pub struct RequestExtensions {
+    map: HashMap<TypeId, Box<dyn Any + Send + Sync>>
+}
+
+impl Debug for RequestExtensions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestExtensions")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+impl RequestExtensions {
+    /// It inserts a value, replacing and returning the previous value of the same type.
+    ///
+    /// Arguments:
+    ///
+    /// * `value`: The value to attach.
+    ///
+    /// Returns:
+    ///
+    /// The value of the same type that was attached before, if any.
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        self.map.insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous: Box<dyn Any + Send + Sync>| previous.downcast::<T>().ok())
+            .map(|previous: Box<T>| *previous)
+    }
+
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.map.get(&TypeId::of::<T>())
+            .and_then(|value: &Box<dyn Any + Send + Sync>| value.downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.map.get_mut(&TypeId::of::<T>())
+            .and_then(|value: &mut Box<dyn Any + Send + Sync>| value.downcast_mut::<T>())
+    }
+
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        self.map.remove(&TypeId::of::<T>())
+            .and_then(|value: Box<dyn Any + Send + Sync>| value.downcast::<T>().ok())
+            .map(|value: Box<T>| *value)
+    }
+}
@@ -3,6 +3,7 @@ use std::str::{Split, SplitWhitespace};
 
 use crate::web::models::request::request_line_data::request_queries::RequestQueries;
 use crate::web::util::encoders::url_encoder;
+use crate::web::util::parsers::path_parser::normalize_path;
 use crate::web::util::parsers::request_parser::RequestParseError;
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
@@ -36,12 +37,16 @@ mod request_queries;
 ///
 /// * `method`: The HTTP method used in the request.
 /// * `path`: The path of the request.
+/// * `target`: The path with its query, as the client sent it, e.g. for access logs.
+/// * `normalized_path`: The path as `normalize_path` returns it, `None` if it has a `..` segment.
 /// * `protocol`: The protocol used in the request.
 /// * `path_query_bypassed`: This is a boolean that indicates whether the path query has been bypassed.
 #[derive(Debug)]
 pub struct RequestLineData {
     method: String,
     path: String,
+    target: String,
+    normalized_path: Option<String>,
     protocol: String,
     request_queries: Option<RequestQueries>
 }
@@ -110,7 +115,9 @@ impl RequestLineData {
             }?.to_string();
         Ok(Self {
             method,
+            normalized_path: normalize_path(&path),
             path,
+            target: full_path_string,
             protocol,
             request_queries: request_queries_opt
         })
@@ -139,6 +146,18 @@ impl RequestLineData {
     pub fn path(&self) -> &str {
         &self.path
     }
+    /// The path with its query, e.g. "/users?page=2", as it was in the request line, even if the
+    /// path was set since.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+    /// The path percent-decoded, without empty and `.` segments, e.g. "/admin/users" for
+    /// "//%61dmin/./users". Path based checks, like group middleware, should use it instead of
+    /// `path`, so they can't be sidestepped by spelling the path differently. `None` if the path
+    /// has a `..` segment or can't be decoded; the request handler answers those with 400.
+    pub fn normalized_path(&self) -> Option<&str> {
+        self.normalized_path.as_deref()
+    }
     pub fn request_queries(&self) -> &Option<RequestQueries> {
         &self.request_queries
     }
@@ -146,6 +165,7 @@ impl RequestLineData {
         self.method = method;
     }
     pub fn set_path(&mut self, path: String) {
+        self.normalized_path = normalize_path(&path);
         self.path = path;
     }
     pub fn set_protocol(&mut self, protocol: String) {
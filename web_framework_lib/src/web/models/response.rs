@@ -1,12 +1,31 @@
-use std::borrow::Cow;
-use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
-use std::fs;
-use std::io::Error;
-use std::ops::Add;
+use std::ffi::OsStr;
+use std::fs::{File, Metadata};
+use std::io;
+use std::io::{BufWriter, Error, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
 
+use crate::web::models::body::Body;
+use crate::web::models::cookie::Cookie;
+use crate::web::models::etag::ETag;
+use crate::web::models::header_map::HeaderMap;
+use crate::web::models::request::Request;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::request_handling::static_files::{StaticFiles, DEFAULT_ROOT};
+use crate::web::templating::template_engine;
 use crate::web::util::enums::mime_types::MimeTypes;
+use crate::web::util::enums::redirect_kind::RedirectKind;
+use crate::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+use crate::web::util::parsers::http_date_parser::{format_http_date, parse_http_date};
+use crate::web::util::parsers::range_parser::{parse_range, ByteRange, RangeRequest};
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
 // ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
@@ -20,83 +39,83 @@ use crate::web::util::enums::mime_types::MimeTypes;
 //                               ░                    ░
 
 const DEFAULT_HTTP_VERSION: &str = "HTTP/1.1";
+const SERVER: &str = "web_framework_rust";
+/// Headers written by the serializer itself, from the body and the connection.
+const FRAMING_HEADERS: [&str; 5] = ["Content-Length", "Transfer-Encoding", "Date", "Connection", "Server"];
+/// How much of a chunked reader body is read and sent at once.
+const CHUNK_SIZE: usize = 16 * 1024;
 
-/// `Response` is a struct that contains a reference to a string, a `u16`, another reference to a
-/// string, a `HashMap` of references to strings and strings, and a `Vec` of `u8`s.
-///
-/// The `'a` is a lifetime parameter. It's a way of saying that the struct contains references to data
-/// that will live at least as long as the struct itself.
-///
-/// The `&'a str` is a reference to a string that will live at least as long as the struct itself.
+/// Produces the trailers of a chunked response once its body has been sent.
+pub type TrailerFunction = Box<dyn FnOnce() -> HeaderMap + Send>;
+
+/// `Response` is a struct that contains a status, a `HeaderMap`, cookies and a `Body`.
 ///
-/// The `HashMap
+/// It owns all of its data, so handlers can build headers from runtime strings, and return it
+/// independently of the request.
 ///
 /// Properties:
 ///
 /// * `protocol`: The protocol used for the response, e.g. HTTP/1.1
 /// * `status`: The HTTP status code.
-/// * `reason_phrase`: The reason phrase is a human-readable string that is usually
-/// * `headers`: A HashMap of the headers in the response.
-/// * `body`: The body of the response.
-#[derive(Clone)]
-pub struct Response<'a> {
-    protocol: &'a str,
-    status: u16,
-    reason_phrase: &'a str,
-    headers: HashMap<&'a str, String>,
-    body: Vec<u8>
+/// * `reason_phrase`: A custom reason phrase, replacing the canonical one of the status code.
+/// * `headers`: The headers of the response.
+/// * `cookies`: The cookies, sent as `Set-Cookie` headers.
+/// * `body`: The body of the response, in memory or streamed.
+/// * `trailers`: Headers sent after a chunked body.
+pub struct Response {
+    protocol: &'static str,
+    status: StatusCode,
+    reason_phrase: Option<String>,
+    headers: HeaderMap,
+    cookies: Vec<Cookie>,
+    body: Body,
+    trailers: Option<TrailerFunction>
 }
 
-impl Debug for Response<'_> {
+impl Debug for Response {
     fn fmt<'a>(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let body_as_uft8: Cow<str>;
-        body_as_uft8 = String::from_utf8_lossy(self.body().as_slice());
         f.debug_struct("Response")
             .field("protocol", &self.protocol)
             .field("status", &self.status)
-            .field("reason_phrase", &self.reason_phrase)
+            .field("reason_phrase", &self.reason_phrase())
             .field("headers", &self.headers)
-            .field("body", &body_as_uft8)
+            .field("cookies", &self.cookies)
+            .field("body", &self.body)
             .finish()
     }
 }
 
 #[allow(dead_code)]
-impl <'a> Response<'a> {
-    /// `new` is a function that takes a `status` and a `reason_phrase` and returns a `Response` struct
+impl Response {
+    /// `new` is a function that takes a `status` and returns a `Response` struct with the canonical
+    /// reason phrase of the status, e.g. `Response::new(StatusCode::CREATED)`.
     ///
     /// Arguments:
     ///
     /// * `status`: The HTTP status code.
-    /// * `reason_phrase`: The reason phrase is a human-readable string that is usually used to explain
-    /// the status code.
     ///
     /// Returns:
     ///
     /// A new instance of the Response struct.
-    pub fn new(status: u16, reason_phrase: &'a str) -> Self {
+    pub fn new(status: StatusCode) -> Self {
         Self {
             protocol: DEFAULT_HTTP_VERSION,
             status,
-            reason_phrase,
-            headers: HashMap::new(),
-            body: Vec::new()
+            reason_phrase: None,
+            headers: HeaderMap::new(),
+            cookies: Vec::new(),
+            body: Body::default(),
+            trailers: None
         }
     }
 
-    /// > This function creates a new `Response` struct with default values
+    /// > This function creates a new `Response` struct with default values and a `200 OK` status
     ///
     /// Returns:
     ///
     /// A new instance of the Response struct.
     pub fn new_empty() -> Self {
-        Self {
-            protocol: DEFAULT_HTTP_VERSION,
-            status: 0,
-            reason_phrase: "",
-            headers: HashMap::new(),
-            body: Vec::new()
-        }
+        Self::new(StatusCode::OK)
     }
 
     /// `ok()` returns a new `Response` with a status code of 200 and a status message of "OK"
@@ -105,11 +124,10 @@ impl <'a> Response<'a> {
     ///
     /// A new instance of the `Response` struct.
     pub fn ok() -> Self {
-        Self::new(200, "OK")
+        Self::new(StatusCode::OK)
     }
     pub fn set_ok(&mut self) {
-        self.set_status(200);
-        self.set_reason_phrase("OK");
+        self.set_status(StatusCode::OK);
     }
     /// `not_found()` returns a new `Status` with a code of `404` and a message of `Not Found`
     ///
@@ -117,11 +135,10 @@ impl <'a> Response<'a> {
     ///
     /// A new instance of the `HttpResponse` struct.
     pub fn not_found() -> Self {
-        Self::new(404, "Not Found")
+        Self::new(StatusCode::NOT_FOUND)
     }
     pub fn set_not_found(&mut self) {
-        self.set_status(404);
-        self.set_reason_phrase("Not Found");
+        self.set_status(StatusCode::NOT_FOUND);
     }
     /// `bad_request` returns a `Response` with a status code of 400 and a body of `s`
     ///
@@ -133,11 +150,121 @@ impl <'a> Response<'a> {
     ///
     /// A new Response object with a status code of 400 and a body of the string passed in.
     pub fn bad_request(s: &str) -> Self {
-        let mut res: Response = Self::new(400, "Bad Request");
+        let mut res: Response = Self::new(StatusCode::BAD_REQUEST);
         res.set_body(s.to_owned());
         res
     }
 
+    /// `json` returns a `200 OK` with a JSON body and `Content-Type: application/json`. JSON is
+    /// always UTF-8, so it has no charset parameter.
+    ///
+    /// Arguments:
+    ///
+    /// * `json`: The body, e.g. `JsonVariant::JsonObject(object)`.
+    pub fn json(json: &JsonVariant) -> Self {
+        let mut res: Response = Self::ok();
+        res.set_body(json.to_string());
+        res.content_type(MimeTypes::JSON);
+        res
+    }
+
+    /// `html` returns a `200 OK` with an HTML body and `Content-Type: text/html; charset=utf-8`.
+    pub fn html(html: String) -> Self {
+        let mut res: Response = Self::ok();
+        res.set_body(html);
+        res.add_header("Content-Type", format!("{}; charset=utf-8", MimeTypes::HTML.to_string()));
+        res
+    }
+
+    /// `render` returns a `200 OK` with a rendered template, from the templates set with
+    /// `template_engine::set_templates`, or `src/templates` by default. If the template can't be
+    /// rendered, the error is logged and the response is the `500 Internal Server Error` page.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The path of the template, e.g. "users/show.html".
+    /// * `context`: The values the template can use.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use web_framework_lib::web::models::response::Response;
+    /// use web_framework_lib::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+    /// let mut context: JsonObject = JsonObject::new();
+    /// context.insert("name", JsonVariant::from("Ada"));
+    /// let res: Response = Response::render("hello.html", &context);
+    /// ```
+    pub fn render(name: &str, context: &JsonObject) -> Self {
+        match template_engine::templates().render(name, context) {
+            Ok(html) => Self::html(html),
+            Err(e) => {
+                if log::log_enabled!(log::Level::Error) {
+                    log::error!("{}", e);
+                }
+                fallback_handlers::negotiated_error_response(None, StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    /// `text` returns a `200 OK` with a plain text body and `Content-Type: text/plain; charset=utf-8`.
+    pub fn text(text: String) -> Self {
+        let mut res: Response = Self::ok();
+        res.set_body(text);
+        res.add_header("Content-Type", format!("{}; charset=utf-8", MimeTypes::TXT.to_string()));
+        res
+    }
+
+    /// `redirect` returns a redirect to another URL, with an empty body.
+    ///
+    /// Arguments:
+    ///
+    /// * `location`: Where to go, an absolute URL or a path, e.g. "/login".
+    /// * `kind`: The kind of redirect, e.g. `RedirectKind::SeeOther` after a form post.
+    ///
+    /// Returns:
+    ///
+    /// A Response with the status of the kind and a `Location` header.
+    pub fn redirect(location: &str, kind: RedirectKind) -> Self {
+        let mut res: Response = Self::new(kind.status());
+        res.add_header("Location", location.to_string());
+        res
+    }
+
+    /// `created` returns a `201 Created` pointing at the new resource, with an empty body.
+    ///
+    /// Arguments:
+    ///
+    /// * `location`: The URL of the created resource, e.g. "/users/42".
+    pub fn created(location: &str) -> Self {
+        let mut res: Response = Self::new(StatusCode::CREATED);
+        res.add_header("Location", location.to_string());
+        res
+    }
+
+    /// `no_content` returns a `204 No Content`, e.g. after a delete. It never has a body.
+    pub fn no_content() -> Self {
+        Self::new(StatusCode::NO_CONTENT)
+    }
+
+    /// `attachment` returns a `200 OK` with a file from the public folder that browsers download
+    /// instead of showing, like `set_body_to_file`, with a `Content-Disposition` header. Names
+    /// with characters other than ASCII are sent as `filename*`, with an ASCII fallback for old
+    /// clients.
+    ///
+    /// Arguments:
+    ///
+    /// * `path_from_public`: The path to the file, relative to the public folder.
+    /// * `filename`: The name the file is saved as, e.g. "Rechnung März.pdf".
+    ///
+    /// Returns:
+    ///
+    /// The Response, or the error of opening the file.
+    pub fn attachment(path_from_public: &str, filename: &str) -> Result<Self, Error> {
+        let mut res: Response = Self::ok();
+        res.set_body_to_file(path_from_public)?;
+        res.add_header("Content-Disposition", content_disposition(filename));
+        Ok(res)
+    }
+
     /// > This function takes a path to a file in the `public` directory and sets the response body to
     /// the contents of that file, it also sets the "Content-Type" header based on file ext.
     ///
@@ -148,45 +275,232 @@ impl <'a> Response<'a> {
     /// Returns:
     ///
     /// A Result<(), Error>
+    ///
+    /// The file is streamed from disk while the response is sent, instead of being read into
+    /// memory, and `Accept-Ranges: bytes` tells clients they can ask for parts of it, e.g. to seek
+    /// in a video. It also sets a strong `ETag` from the size and modification time of the file,
+    /// and `Last-Modified`, so `ConditionalRequests` can answer with 304.
+    ///
+    /// The path is checked like a request path by `StaticFiles`, so it is percent-decoded and
+    /// can't leave the public folder, even when it's built from user input.
     /// # Examples
-    /// ```
-    /// use web_framework_lib::web::server::data::models::transaction::response::Response;
+    /// ```no_run
+    /// use web_framework_lib::web::models::response::Response;
     /// let mut res: Response = Response::new_empty();
     /// res.set_body_to_file("index.html").expect("");
     /// res.set_body_to_file("/index.html").expect("");
+    /// ```
     pub fn set_body_to_file(&mut self, path_from_public: &str) -> Result<(), Error> {
-        let mut path_prefix: String = "src/public".to_string();
-        let mime_type: String = path_from_public.rsplit_once('.')
-            .map(|(_parent_path, ext): (&str, &str)| {
-                let ext: String = ext.replace(".download", "");
-                let e: MimeTypes = MimeTypes::from_str(&ext)
-                    .unwrap_or_else(|()| panic!("Invalid ext: {}", ext));
-                e.to_string()
-            }).expect("Failed to get mime type.");
-        if !path_from_public.starts_with('/') { path_prefix = path_prefix.add("/"); }
-        match fs::read(path_prefix.add(path_from_public)) {
-            Ok(t) => {
-                self.set_body_u8(t);
-                self.add_header("Content-Type", mime_type);
-                Ok(())
+        let path_from_public: String = match path_from_public.starts_with('/') {
+            true => path_from_public.to_string(),
+            false => format!("/{}", path_from_public)
+        };
+        let path: PathBuf = StaticFiles::new(DEFAULT_ROOT)?
+            .resolve(&path_from_public)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No such file in {}: {}", DEFAULT_ROOT, path_from_public)))?;
+        self.set_body_to_path(&path)
+    }
+
+    /// It sets the response body to a file anywhere on disk, like `set_body_to_file` does for the
+    /// public folder. The path is used as it is, so it must not come from the request; use
+    /// `StaticFiles` to serve files for request paths.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path to the file.
+    ///
+    /// Returns:
+    ///
+    /// A Result<(), Error>, with `ErrorKind::InvalidInput` if the path isn't a file.
+    pub fn set_body_to_path(&mut self, path: &Path) -> Result<(), Error> {
+        let mime_type: String = path.extension()
+            .and_then(|ext: &OsStr| ext.to_str())
+            .and_then(|ext: &str| MimeTypes::from_str(ext).ok())
+            .map(|mime_type: MimeTypes| mime_type.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let file: File = File::open(path)?;
+        let metadata: Metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Not a file: {}", path.display())));
+        }
+        let modified: Option<SystemTime> = metadata.modified().ok();
+        let modified_nanos: u128 = modified
+            .and_then(|modified: SystemTime| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_nanos())
+            .unwrap_or(0);
+        let digest = Sha256::digest(format!("{}-{}", metadata.len(), modified_nanos));
+        self.set_etag(ETag::strong(&URL_SAFE_NO_PAD.encode(&digest[..16])));
+        if let Some(modified) = modified {
+            self.set_last_modified(modified);
+        }
+        self.body = Body::File(file, metadata.len());
+        self.add_header("Content-Type", mime_type);
+        self.add_header("Accept-Ranges", "bytes".to_string());
+        Ok(())
+    }
+
+    /// It answers a `Range` request for a file body, set by `set_body_to_file` or
+    /// `Body::from_file`. A single range gets `206 Partial Content` with `Content-Range`, several
+    /// ranges a `multipart/byteranges` body, and ranges outside the file `416 Range Not
+    /// Satisfiable`. With `If-Range`, the ranges are only sent if the file still has the given
+    /// `ETag` or `Last-Modified` date, otherwise the whole file is.
+    ///
+    /// The request handler calls it for every response, so handlers don't have to. Other requests
+    /// and responses are left as they are.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request being answered.
+    pub fn apply_range(&mut self, req: &Request) -> &mut Self {
+        if req.request_line_data().method() != "GET" || self.status != StatusCode::OK
+            || !matches!(self.body, Body::File(..)) {
+            return self;
+        }
+        let range: &str = match req.get_header("Range") {
+            Some(range) => range,
+            None => return self
+        };
+        if req.get_header("If-Range").is_some_and(|if_range: &str| !self.if_range_matches(if_range)) {
+            return self;
+        }
+        let length: u64 = self.body.length().unwrap_or(0);
+        match parse_range(range, length) {
+            RangeRequest::Full => { },
+            RangeRequest::Unsatisfiable => {
+                self.set_status(StatusCode::RANGE_NOT_SATISFIABLE);
+                self.add_header("Content-Range", format!("bytes */{}", length));
+                self.remove_header("Content-Type");
+                self.body = Body::default();
             },
-            Err(e) => {
-                Err(e)
+            RangeRequest::Partial(ranges) => {
+                let Body::File(file, _) = std::mem::take(&mut self.body) else { return self; };
+                if let Err(e) = self.set_ranges(file, length, &ranges) {
+                    if log::log_enabled!(log::Level::Error) {
+                        log::error!("Failed to send ranges of file: {}", e);
+                    }
+                    *self = Response::new(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+        self
+    }
+
+    /// It checks an `If-Range` header, which holds either a strong `ETag` or an exact date.
+    fn if_range_matches(&self, if_range: &str) -> bool {
+        match ETag::parse(if_range) {
+            Some(tag) => self.etag().is_some_and(|etag: ETag| etag.strong_eq(&tag)),
+            None => match (parse_http_date(if_range), self.last_modified()) {
+                (Some(date), Some(last_modified)) => date == last_modified,
+                _ => false
             }
         }
     }
 
-    /// `add_header` takes a mutable reference to a `Request` struct and two strings, one of which is
-    /// borrowed, and inserts the key and value into the `headers` field of the `Request` struct
+    /// It turns the response into a `206 Partial Content` with the ranges of the file.
+    fn set_ranges(&mut self, mut file: File, length: u64, ranges: &[ByteRange]) -> io::Result<()> {
+        self.set_status(StatusCode::PARTIAL_CONTENT);
+        if let [range] = ranges {
+            file.seek(SeekFrom::Start(range.start()))?;
+            self.add_header("Content-Range", format!("bytes {}-{}/{}", range.start(), range.end(), length));
+            self.body = Body::File(file, range.length());
+            return Ok(());
+        }
+        let mut bytes: [u8; 12] = [0; 12];
+        OsRng.fill_bytes(&mut bytes);
+        let boundary: String = URL_SAFE_NO_PAD.encode(bytes);
+        let content_type: String = self.remove_header("Content-Type")
+            .unwrap_or_else(|| MimeTypes::BIN.to_string());
+        self.body = Body::from_file_ranges(file, length, ranges, &content_type, &boundary);
+        self.add_header("Content-Type", format!("multipart/byteranges; boundary={}", boundary));
+        Ok(())
+    }
+
+    /// `add_header` sets a header, replacing the values it had. Use `append_header` for headers
+    /// with several values.
     ///
     /// Arguments:
     ///
-    /// * `key`: &'a str
+    /// * `key`: The case-insensitive name of the header.
     /// * `val`: String - The value of the header.
-    pub fn add_header(&mut self, key: &'a str, val: String) {
+    pub fn add_header(&mut self, key: &str, val: String) {
         self.headers.insert(key, val);
     }
 
+    /// It adds a value to a header, keeping the values it already has, e.g. for `Link`.
+    ///
+    /// Arguments:
+    ///
+    /// * `key`: The case-insensitive name of the header.
+    /// * `val`: The value to add.
+    pub fn append_header(&mut self, key: &str, val: String) {
+        self.headers.append(key, val);
+    }
+
+    /// It removes every value of a header, returning the first one.
+    pub fn remove_header(&mut self, key: &str) -> Option<String> {
+        self.headers.remove(key)
+    }
+
+    /// It sets the `ETag` header, identifying the version of the resource for conditional requests.
+    pub fn set_etag(&mut self, etag: ETag) -> &mut Self {
+        self.headers.insert("ETag", etag.to_string());
+        self
+    }
+
+    /// It parses the `ETag` header, if it is set.
+    pub fn etag(&self) -> Option<ETag> {
+        self.headers.get("ETag").and_then(ETag::parse)
+    }
+
+    /// It sets the `Last-Modified` header. HTTP dates have a resolution of seconds.
+    pub fn set_last_modified(&mut self, last_modified: SystemTime) -> &mut Self {
+        self.headers.insert("Last-Modified", format_http_date(last_modified));
+        self
+    }
+
+    /// It parses the `Last-Modified` header, if it is set.
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.headers.get("Last-Modified").and_then(parse_http_date)
+    }
+
+    /// It adds a request header to the `Vary` header, keeping the ones already listed, so caches
+    /// know the response depends on it.
+    ///
+    /// Arguments:
+    ///
+    /// * `header`: The name of the request header, e.g. "Accept-Encoding".
+    pub fn add_vary(&mut self, header: &str) -> &mut Self {
+        let vary: String = match self.headers.get("Vary") {
+            Some(vary) if vary.split(',').any(|v: &str| v.trim().eq_ignore_ascii_case(header)) => return self,
+            Some(vary) if !vary.trim().is_empty() => format!("{}, {}", vary, header),
+            _ => header.to_string()
+        };
+        self.headers.insert("Vary", vary);
+        self
+    }
+
+    /// It adds a cookie, sent as its own `Set-Cookie` header. A cookie with the same name, domain
+    /// and path replaces the one added before.
+    ///
+    /// Arguments:
+    ///
+    /// * `cookie`: The cookie to send.
+    pub fn set_cookie(&mut self, cookie: Cookie) -> &mut Self {
+        self.cookies.retain(|existing: &Cookie| !existing.same_slot(&cookie));
+        self.cookies.push(cookie);
+        self
+    }
+
+    /// It tells the client to delete a cookie set with the default domain and `Path=/`. Use
+    /// `set_cookie(Cookie::removal(name))` with a matching path and domain for other cookies.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the cookie.
+    pub fn remove_cookie(&mut self, name: &str) -> &mut Self {
+        self.set_cookie(Cookie::removal(name).path("/"))
+    }
+
     pub fn content_type(&mut self, mime: MimeTypes) {
         self.headers.insert("Content-Type", mime.to_string());
     }
@@ -206,54 +520,423 @@ impl <'a> Response<'a> {
     ///
     /// * `body`: The body of the request.
     pub fn set_body_u8(&mut self, body: Vec<u8>) {
+        self.body = Body::Bytes(body);
+    }
+
+    /// It sets a body that is streamed to the client while the response is sent, e.g.
+    /// `Body::from_file` or `Body::from_chunks`, so it never has to be in memory at once.
+    ///
+    /// Arguments:
+    ///
+    /// * `body`: The body of the response.
+    pub fn set_body_stream(&mut self, body: Body) -> &mut Self {
         self.body = body;
+        self
+    }
+
+    /// It reads a streamed body of known length into memory, if it isn't longer than `limit`, so
+    /// middleware can work on the bytes, e.g. compress a file from `set_body_to_file`. Bodies of
+    /// unknown length, and longer ones, are left as they are.
+    ///
+    /// Arguments:
+    ///
+    /// * `limit`: The longest body in bytes that is read into memory.
+    ///
+    /// Returns:
+    ///
+    /// Whether the body is in memory now, or the error of reading it, which leaves the body empty.
+    pub fn buffer_body(&mut self, limit: u64) -> io::Result<bool> {
+        let bytes: Vec<u8> = match std::mem::take(&mut self.body) {
+            Body::File(file, length) if length <= limit => read_exactly(file, length)?,
+            Body::Reader(reader, Some(length)) if length <= limit => read_exactly(reader, length)?,
+            body => {
+                self.body = body;
+                return Ok(!self.body.is_streaming());
+            }
+        };
+        self.body = Body::Bytes(bytes);
+        Ok(true)
+    }
+
+    /// It sets the trailers, headers sent after the body, e.g. a checksum of a streamed export.
+    /// They are produced once the body is sent, and only sent with chunked bodies. List their names
+    /// in a `Trailer` header, so clients know to expect them.
+    ///
+    /// Arguments:
+    ///
+    /// * `trailers`: A function producing the trailers.
+    pub fn set_trailers<F: FnOnce() -> HeaderMap + Send + 'static>(&mut self, trailers: F) -> &mut Self {
+        self.trailers = Some(Box::new(trailers));
+        self
     }
 
-    /// It takes the response object and converts it into a vector of bytes
+    /// It serializes the response into a vector of bytes, e.g. to send it later. It's empty if a
+    /// header is invalid, see `write_to`.
     ///
     /// Returns:
     ///
     /// A vector of bytes.
     pub fn get_as_u8_vec(&mut self) -> Vec<u8> {
-        if self.status == 0 {
-            panic!("Please mutate http status before getting as byte vector.")
+        let mut res_as_u8_vec: Vec<u8> = Vec::with_capacity(self.body().len() + 256);
+        if self.write_to(&mut res_as_u8_vec).is_err() {
+            res_as_u8_vec.clear();
         }
-        let mut header_map_to_str: String = String::new();
-        for (k, v) in &self.headers {
-            header_map_to_str = header_map_to_str
-                .add(k).add(": ").add(v.as_str()).add("\r\n")
-        };
-        let mut res_as_u8_vec: Vec<u8> =
-            Vec::from(format!("{proto} {status} {reason}\r\n{headers}\r\n",
-                              proto=self.protocol,
-                              status=self.status,
-                              reason=self.reason_phrase,
-                              headers=header_map_to_str).as_bytes());
-        res_as_u8_vec.append(&mut self.body.clone()); // Cloning for debugging purposes.
         res_as_u8_vec
     }
+
+    /// It writes the response to a connection. The framing headers are added automatically:
+    /// `Content-Length` from the body, or `Transfer-Encoding: chunked` for streamed bodies of unknown
+    /// length, except for 1xx, 204 and 304 responses which have no body,
+    /// `Date` and `Server` unless they are set, and `Connection: close`, since the connection is
+    /// closed after every response.
+    ///
+    /// Nothing is written if a header name, header value, cookie or reason phrase contains a CR or
+    /// LF, since that would let its content inject headers or a whole response (response
+    /// splitting).
+    ///
+    /// Arguments:
+    ///
+    /// * `writer`: Where the response is written, e.g. a `TcpStream`.
+    ///
+    /// Returns:
+    ///
+    /// An error with `ErrorKind::InvalidData` if a header is invalid, or the error of the writer or
+    /// of a streamed body. A streamed body is consumed, even if it fails halfway, in which case the
+    /// client can tell the body is incomplete.
+    pub fn write_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.write(writer, true)
+    }
+
+    /// It writes the response without its body, for `HEAD` requests. `Content-Length` still tells
+    /// the length of the body a `GET` would get.
+    pub fn write_head_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.write(writer, false)
+    }
+
+    /// It returns the name of the first header that can't be sent, if any. See `write_to`.
+    pub fn invalid_header(&self) -> Option<&str> {
+        if !is_valid_header_value(self.reason_phrase()) {
+            return Some("Reason-Phrase");
+        }
+        self.headers.iter()
+            .find(|(name, value)| !is_valid_header_name(name) || !is_valid_header_value(value))
+            .map(|(name, _)| name)
+            .or_else(|| self.cookies.iter()
+                .any(|cookie: &Cookie| !is_valid_header_value(&cookie.to_string()))
+                .then_some("Set-Cookie"))
+    }
+
+    fn write<W: Write>(&mut self, writer: &mut W, with_body: bool) -> io::Result<()> {
+        if let Some(name) = self.invalid_header() {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid header: {:?}", name)));
+        }
+        let has_body: bool = !(self.status.is_informational() || self.status == StatusCode::NO_CONTENT
+            || self.status == StatusCode::NOT_MODIFIED);
+        let mut head: BufWriter<&mut W> = BufWriter::new(writer);
+        write!(head, "{} {} {}\r\n", self.protocol, self.status, self.reason_phrase())?;
+        for (name, value) in self.headers.iter() {
+            if !FRAMING_HEADERS.iter().any(|framing: &&str| framing.eq_ignore_ascii_case(name)) {
+                write!(head, "{}: {}\r\n", name, value)?;
+            }
+        }
+        for cookie in &self.cookies {
+            write!(head, "Set-Cookie: {}\r\n", cookie)?;
+        }
+        match (has_body, self.body.length()) {
+            (true, Some(length)) => write!(head, "Content-Length: {}\r\n", length)?,
+            (true, None) => write!(head, "Transfer-Encoding: chunked\r\n")?,
+            (false, _) => {}
+        }
+        match self.headers.get("Date") {
+            Some(date) => write!(head, "Date: {}\r\n", date)?,
+            None => write!(head, "Date: {}\r\n", format_http_date(SystemTime::now()))?
+        }
+        write!(head, "Connection: close\r\nServer: {}\r\n\r\n", self.headers.get("Server").unwrap_or(SERVER))?;
+        let writer: &mut W = head.into_inner().map_err(|e| e.into_error())?;
+        if has_body && with_body {
+            self.write_body(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// It writes the body, consuming it if it's streamed.
+    fn write_body<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if let Body::Bytes(bytes) = &self.body {
+            return writer.write_all(bytes);
+        }
+        match std::mem::take(&mut self.body) {
+            Body::Bytes(_) => Ok(()),
+            Body::Reader(reader, Some(length)) => copy_exactly(Read::take(reader, length), writer, length),
+            Body::File(file, length) => copy_exactly(Read::take(file, length), writer, length),
+            Body::Reader(mut reader, None) => {
+                let mut buffer: Vec<u8> = vec![0; CHUNK_SIZE];
+                loop {
+                    let read: usize = match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(read) => read,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e)
+                    };
+                    write_chunk(writer, &buffer[..read])?;
+                }
+                self.write_last_chunk(writer)
+            },
+            Body::Chunks(chunks) => {
+                for chunk in chunks {
+                    write_chunk(writer, &chunk?)?;
+                }
+                self.write_last_chunk(writer)
+            }
+        }
+    }
+
+    /// It ends a chunked body, with the trailers if there are any. Trailers with invalid names or
+    /// values are left out, since the head was already sent.
+    fn write_last_chunk<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b"0\r\n")?;
+        if let Some(trailers) = self.trailers.take() {
+            for (name, value) in trailers().iter() {
+                if is_valid_header_name(name) && is_valid_header_value(value) {
+                    write!(writer, "{}: {}\r\n", name, value)?;
+                } else if log::log_enabled!(log::Level::Warn) {
+                    log::warn!("Left out invalid trailer: {:?}", name);
+                }
+            }
+        }
+        writer.write_all(b"\r\n")
+    }
+
     pub fn protocol(&self) -> &str {
         self.protocol
     }
-    pub fn status(&self) -> u16 {
+    pub fn status(&self) -> StatusCode {
         self.status
     }
+    /// The custom reason phrase if one was set, or the canonical one of the status code.
     pub fn reason_phrase(&self) -> &str {
-        self.reason_phrase
+        self.reason_phrase.as_deref().unwrap_or_else(|| self.status.reason_phrase())
+    }
+    /// The body if it's in memory, or an empty slice if it's streamed.
+    pub fn body(&self) -> &[u8] {
+        match &self.body {
+            Body::Bytes(bytes) => bytes,
+            _ => &[]
+        }
+    }
+    /// The length of the body, or `None` for a streamed body of unknown length.
+    pub fn body_length(&self) -> Option<u64> {
+        self.body.length()
     }
-    pub fn body(&self) -> &Vec<u8> {
-        &self.body
+    pub fn is_streaming(&self) -> bool {
+        self.body.is_streaming()
     }
-    pub fn headers(&self) -> &HashMap<&'a str, String> {
+    pub fn headers(&self) -> &HeaderMap {
         &self.headers
     }
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+    pub fn cookies(&self) -> &Vec<Cookie> {
+        &self.cookies
+    }
 
-    pub fn set_status(&mut self, status: u16) -> &mut Self {
+    /// It sets the status, along with its canonical reason phrase.
+    pub fn set_status(&mut self, status: StatusCode) -> &mut Self {
         self.status = status;
+        self.reason_phrase = None;
         self
     }
-    pub fn set_reason_phrase(&mut self, reason_phrase: &'a str) -> &mut Self {
-        self.reason_phrase = reason_phrase;
+    /// It replaces the canonical reason phrase of the status, e.g. for an unregistered status code.
+    pub fn set_reason_phrase(&mut self, reason_phrase: &str) -> &mut Self {
+        self.reason_phrase = Some(reason_phrase.to_string());
         self
     }
-}
\ No newline at end of file
+}
+
+/// It copies a body of a known length, failing if it ends early. Files are copied to TCP streams with
+/// `sendfile` on Linux, since `io::copy` specializes for them.
+fn copy_exactly<R: Read, W: Write>(mut reader: R, writer: &mut W, length: u64) -> io::Result<()> {
+    let copied: u64 = io::copy(&mut reader, writer)?;
+    if copied < length {
+        return Err(io::Error::new(ErrorKind::UnexpectedEof, format!("Body ended after {} of {} bytes", copied, length)));
+    }
+    Ok(())
+}
+
+/// It reads a body of a known length into memory, failing if it ends early, like `copy_exactly`.
+fn read_exactly<R: Read>(reader: R, length: u64) -> io::Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(length as usize);
+    copy_exactly(Read::take(reader, length), &mut bytes, length)?;
+    Ok(bytes)
+}
+
+/// It builds the `Content-Disposition` of an attachment. The `filename` is limited to printable ASCII
+/// without quotes or backslashes, and the exact name is added as a UTF-8 `filename*` (RFC 6266)
+/// when it differs.
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename.chars()
+        .map(|c: char| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+    if fallback == filename {
+        return format!("attachment; filename=\"{}\"", fallback);
+    }
+    let encoded: String = filename.bytes()
+        .map(|b: u8| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9'
+            | b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b)
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+/// It writes a chunk of a chunked body. Empty chunks are skipped, since they would end the body.
+fn write_chunk<W: Write>(writer: &mut W, chunk: &[u8]) -> io::Result<()> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    write!(writer, "{:X}\r\n", chunk.len())?;
+    writer.write_all(chunk)?;
+    writer.write_all(b"\r\n")
+}
+
+/// Header names are tokens, e.g. no spaces, colons or line breaks.
+fn is_valid_header_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b: u8| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+/// Header values can't break the line they're on.
+fn is_valid_header_value(value: &str) -> bool {
+    !value.bytes().any(|b: u8| b == b'\r' || b == b'\n' || b == 0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+    use crate::web::models::body::Body;
+    use crate::web::models::etag::ETag;
+    use crate::web::models::header_map::HeaderMap;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::{content_disposition, Response};
+    use crate::web::models::status_code::StatusCode;
+    use crate::web::util::enums::redirect_kind::RedirectKind;
+    use crate::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+
+    #[test]
+    fn adds_framing_headers() {
+        let mut res: Response = Response::ok();
+        res.add_header("Content-Length", "999".to_string());
+        res.add_header("X-First", "1".to_string());
+        res.append_header("X-First", "2".to_string());
+        res.set_body("hello".to_string());
+        let serialized: String = String::from_utf8(res.get_as_u8_vec()).unwrap();
+        assert!(serialized.starts_with("HTTP/1.1 200 OK\r\nX-First: 1\r\nX-First: 2\r\nContent-Length: 5\r\nDate: "));
+        assert!(serialized.ends_with("\r\nConnection: close\r\nServer: web_framework_rust\r\n\r\nhello"));
+        assert!(!serialized.contains("999"));
+
+        let mut head: Vec<u8> = Vec::new();
+        res.write_head_to(&mut head).unwrap();
+        assert!(String::from_utf8(head).unwrap().ends_with("\r\n\r\n"));
+
+        let mut not_modified: Response = Response::new(StatusCode::NOT_MODIFIED);
+        not_modified.set_body("ignored".to_string());
+        let serialized: String = String::from_utf8(not_modified.get_as_u8_vec()).unwrap();
+        assert!(!serialized.contains("Content-Length") && serialized.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn rejects_line_breaks() {
+        let mut res: Response = Response::ok();
+        res.add_header("Location", "/a\r\nSet-Cookie: admin=1".to_string());
+        assert_eq!(res.invalid_header(), Some("Location"));
+        let mut written: Vec<u8> = Vec::new();
+        assert!(res.write_to(&mut written).is_err());
+        assert!(written.is_empty());
+
+        let mut res: Response = Response::ok();
+        res.add_header("X-Bad Name", "1".to_string());
+        assert_eq!(res.invalid_header(), Some("X-Bad Name"));
+    }
+
+    #[test]
+    fn streams_bodies() {
+        let mut res: Response = Response::ok();
+        res.set_body_stream(Body::from_reader(Cursor::new(b"hello world".to_vec()), Some(5)));
+        assert!(res.is_streaming() && res.body().is_empty());
+        let serialized: String = String::from_utf8(res.get_as_u8_vec()).unwrap();
+        assert!(serialized.contains("Content-Length: 5\r\n") && serialized.ends_with("\r\n\r\nhello"));
+
+        let chunks: Vec<std::io::Result<Vec<u8>>> = vec![Ok(b"abc".to_vec()), Ok(Vec::new()), Ok(b"0123456789ab".to_vec())];
+        let mut res: Response = Response::ok();
+        res.set_body_stream(Body::from_chunks(chunks.into_iter()))
+            .set_trailers(|| {
+                let mut trailers: HeaderMap = HeaderMap::new();
+                trailers.insert("X-Checksum", "42".to_string());
+                trailers.insert("X-Bad", "a\r\nb".to_string());
+                trailers
+            });
+        assert_eq!(res.body_length(), None);
+        let serialized: String = String::from_utf8(res.get_as_u8_vec()).unwrap();
+        assert!(!serialized.contains("Content-Length"));
+        assert!(serialized.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(serialized.ends_with("\r\n\r\n3\r\nabc\r\nC\r\n0123456789ab\r\n0\r\nX-Checksum: 42\r\n\r\n"));
+    }
+
+    #[test]
+    fn answers_ranges() {
+        let path = std::env::temp_dir().join(format!("ranges-{}.txt", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+        let respond = |headers: &str| -> String {
+            let req: Request = Request::new_without_stream(format!("GET /file HTTP/1.1\n{}", headers).as_bytes(), b"").unwrap();
+            let mut res: Response = Response::ok();
+            res.set_body_stream(Body::from_file(&path).unwrap()).set_etag(ETag::strong("v1"));
+            res.add_header("Content-Type", "text/plain".to_string());
+            res.apply_range(&req);
+            String::from_utf8(res.get_as_u8_vec()).unwrap()
+        };
+
+        let single: String = respond("Range: bytes=2-4");
+        assert!(single.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+        assert!(single.contains("Content-Range: bytes 2-4/10\r\n") && single.contains("Content-Length: 3\r\n"));
+        assert!(single.ends_with("\r\n\r\n234"));
+
+        let multiple: String = respond("Range: bytes=0-1, -2");
+        let boundary: &str = multiple.split("boundary=").nth(1).unwrap().split("\r\n").next().unwrap();
+        let body: &str = multiple.split_once("\r\n\r\n").unwrap().1;
+        assert_eq!(body, format!("--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+                                  --{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n--{b}--\r\n", b = boundary));
+        assert!(multiple.contains(&format!("Content-Length: {}\r\n", body.len())));
+
+        let unsatisfiable: String = respond("Range: bytes=10-");
+        assert!(unsatisfiable.starts_with("HTTP/1.1 416 Range Not Satisfiable\r\n"));
+        assert!(unsatisfiable.contains("Content-Range: bytes */10\r\n"));
+
+        assert!(respond("Range: bytes=2-4\nIf-Range: \"v1\"").starts_with("HTTP/1.1 206"));
+        let changed: String = respond("Range: bytes=2-4\nIf-Range: \"v0\"");
+        assert!(changed.starts_with("HTTP/1.1 200 OK\r\n") && changed.ends_with("0123456789"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn builds_common_responses() {
+        let mut object: JsonObject = JsonObject::new();
+        object.insert("id", JsonVariant::JsonString("42".to_string()));
+        let res: Response = Response::json(&JsonVariant::JsonObject(object));
+        assert_eq!(res.headers().get("Content-Type"), Some("application/json"));
+        assert_eq!(res.body(), br#"{"id":"42"}"#);
+
+        assert_eq!(Response::html("<p>hi</p>".to_string()).headers().get("content-type"), Some("text/html; charset=utf-8"));
+        assert_eq!(Response::text("hi".to_string()).headers().get("content-type"), Some("text/plain; charset=utf-8"));
+
+        let res: Response = Response::redirect("/login", RedirectKind::SeeOther);
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get("Location"), Some("/login"));
+        assert_eq!(Response::redirect("/", RedirectKind::PermanentRedirect).status(), 308);
+        assert_eq!(Response::created("/users/42").status(), StatusCode::CREATED);
+        assert_eq!(Response::no_content().status(), StatusCode::NO_CONTENT);
+
+        assert_eq!(content_disposition("report.pdf"), "attachment; filename=\"report.pdf\"");
+        assert_eq!(content_disposition("Rechnung März \"1\".pdf"),
+                   "attachment; filename=\"Rechnung M_rz _1_.pdf\"; filename*=UTF-8''Rechnung%20M%C3%A4rz%20%221%22.pdf");
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt::{Display, Formatter};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `StatusCode` is an HTTP status code, always in the valid range of 100 to 599. Registered codes
+/// have constants, e.g. `StatusCode::NOT_FOUND`, and custom codes are checked by `from_u16`.
+///
+/// It displays as its three digits, and compares to plain numbers, e.g. `res.status() == 404`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCode(u16);
+
+/// The error of `StatusCode::from_u16` for numbers outside 100 to 599.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidStatusCode(u16);
+
+impl InvalidStatusCode {
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+}
+
+impl Display for InvalidStatusCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid status code: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidStatusCode {}
+
+/// It declares a constant for every registered status code, and the table of canonical reason
+/// phrases.
+macro_rules! status_codes {
+    ($(($code: literal, $name: ident, $reason: literal)),* $(,)?) => {
+        impl StatusCode {
+            $(
+                #[doc = concat!("`", stringify!($code), " ", $reason, "`")]
+                pub const $name: StatusCode = StatusCode($code);
+            )*
+
+            /// The reason phrase registered for the code, e.g. "Not Found" for 404, or `None` for
+            /// unregistered codes.
+            pub fn canonical_reason(&self) -> Option<&'static str> {
+                match self.0 {
+                    $($code => Some($reason),)*
+                    _ => None
+                }
+            }
+        }
+    }
+}
+
+status_codes! {
+    (100, CONTINUE, "Continue"),
+    (101, SWITCHING_PROTOCOLS, "Switching Protocols"),
+    (102, PROCESSING, "Processing"),
+    (103, EARLY_HINTS, "Early Hints"),
+    (200, OK, "OK"),
+    (201, CREATED, "Created"),
+    (202, ACCEPTED, "Accepted"),
+    (203, NON_AUTHORITATIVE_INFORMATION, "Non-Authoritative Information"),
+    (204, NO_CONTENT, "No Content"),
+    (205, RESET_CONTENT, "Reset Content"),
+    (206, PARTIAL_CONTENT, "Partial Content"),
+    (207, MULTI_STATUS, "Multi-Status"),
+    (208, ALREADY_REPORTED, "Already Reported"),
+    (226, IM_USED, "IM Used"),
+    (300, MULTIPLE_CHOICES, "Multiple Choices"),
+    (301, MOVED_PERMANENTLY, "Moved Permanently"),
+    (302, FOUND, "Found"),
+    (303, SEE_OTHER, "See Other"),
+    (304, NOT_MODIFIED, "Not Modified"),
+    (305, USE_PROXY, "Use Proxy"),
+    (307, TEMPORARY_REDIRECT, "Temporary Redirect"),
+    (308, PERMANENT_REDIRECT, "Permanent Redirect"),
+    (400, BAD_REQUEST, "Bad Request"),
+    (401, UNAUTHORIZED, "Unauthorized"),
+    (402, PAYMENT_REQUIRED, "Payment Required"),
+    (403, FORBIDDEN, "Forbidden"),
+    (404, NOT_FOUND, "Not Found"),
+    (405, METHOD_NOT_ALLOWED, "Method Not Allowed"),
+    (406, NOT_ACCEPTABLE, "Not Acceptable"),
+    (407, PROXY_AUTHENTICATION_REQUIRED, "Proxy Authentication Required"),
+    (408, REQUEST_TIMEOUT, "Request Timeout"),
+    (409, CONFLICT, "Conflict"),
+    (410, GONE, "Gone"),
+    (411, LENGTH_REQUIRED, "Length Required"),
+    (412, PRECONDITION_FAILED, "Precondition Failed"),
+    (413, CONTENT_TOO_LARGE, "Content Too Large"),
+    (414, URI_TOO_LONG, "URI Too Long"),
+    (415, UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type"),
+    (416, RANGE_NOT_SATISFIABLE, "Range Not Satisfiable"),
+    (417, EXPECTATION_FAILED, "Expectation Failed"),
+    (418, IM_A_TEAPOT, "I'm a teapot"),
+    (421, MISDIRECTED_REQUEST, "Misdirected Request"),
+    (422, UNPROCESSABLE_CONTENT, "Unprocessable Content"),
+    (423, LOCKED, "Locked"),
+    (424, FAILED_DEPENDENCY, "Failed Dependency"),
+    (425, TOO_EARLY, "Too Early"),
+    (426, UPGRADE_REQUIRED, "Upgrade Required"),
+    (428, PRECONDITION_REQUIRED, "Precondition Required"),
+    (429, TOO_MANY_REQUESTS, "Too Many Requests"),
+    (431, REQUEST_HEADER_FIELDS_TOO_LARGE, "Request Header Fields Too Large"),
+    (451, UNAVAILABLE_FOR_LEGAL_REASONS, "Unavailable For Legal Reasons"),
+    (500, INTERNAL_SERVER_ERROR, "Internal Server Error"),
+    (501, NOT_IMPLEMENTED, "Not Implemented"),
+    (502, BAD_GATEWAY, "Bad Gateway"),
+    (503, SERVICE_UNAVAILABLE, "Service Unavailable"),
+    (504, GATEWAY_TIMEOUT, "Gateway Timeout"),
+    (505, HTTP_VERSION_NOT_SUPPORTED, "HTTP Version Not Supported"),
+    (506, VARIANT_ALSO_NEGOTIATES, "Variant Also Negotiates"),
+    (507, INSUFFICIENT_STORAGE, "Insufficient Storage"),
+    (508, LOOP_DETECTED, "Loop Detected"),
+    (510, NOT_EXTENDED, "Not Extended"),
+    (511, NETWORK_AUTHENTICATION_REQUIRED, "Network Authentication Required"),
+}
+
+impl StatusCode {
+    /// `from_u16` checks a status code, which must have three digits from 100 to 599. Codes that
+    /// aren't registered are allowed, e.g. 299 for an API's own meaning.
+    ///
+    /// Arguments:
+    ///
+    /// * `code`: The number of the status code.
+    ///
+    /// Returns:
+    ///
+    /// The StatusCode, or an InvalidStatusCode if the number is out of range.
+    pub fn from_u16(code: u16) -> Result<Self, InvalidStatusCode> {
+        if (100..600).contains(&code) {
+            Ok(Self(code))
+        } else {
+            Err(InvalidStatusCode(code))
+        }
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    /// The canonical reason phrase, or an empty one for unregistered codes, which HTTP allows.
+    pub fn reason_phrase(&self) -> &'static str {
+        self.canonical_reason().unwrap_or("")
+    }
+
+    /// 1xx: the request was received, and handling continues.
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+
+    /// 2xx: the request succeeded.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+
+    /// 3xx: the client has to take further action, e.g. follow a redirect.
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+
+    /// 4xx: the request was wrong.
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    /// 5xx: the server failed to handle a valid request.
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+
+impl Default for StatusCode {
+    fn default() -> Self {
+        Self::OK
+    }
+}
+
+impl Display for StatusCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = InvalidStatusCode;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        Self::from_u16(code)
+    }
+}
+
+impl From<StatusCode> for u16 {
+    fn from(status: StatusCode) -> Self {
+        status.0
+    }
+}
+
+impl PartialEq<u16> for StatusCode {
+    fn eq(&self, other: &u16) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<StatusCode> for u16 {
+    fn eq(&self, other: &StatusCode) -> bool {
+        *self == other.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::models::status_code::StatusCode;
+
+    #[test]
+    fn validates_and_classifies() {
+        assert_eq!(StatusCode::from_u16(404), Ok(StatusCode::NOT_FOUND));
+        assert_eq!(StatusCode::NOT_FOUND.reason_phrase(), "Not Found");
+        assert!(StatusCode::from_u16(0).is_err());
+        assert!(StatusCode::from_u16(99).is_err());
+        assert!(StatusCode::from_u16(600).is_err());
+
+        let custom: StatusCode = StatusCode::from_u16(299).unwrap();
+        assert!(custom.is_success());
+        assert_eq!(custom.canonical_reason(), None);
+        assert_eq!(custom.reason_phrase(), "");
+
+        assert!(StatusCode::CONTINUE.is_informational());
+        assert!(StatusCode::PERMANENT_REDIRECT.is_redirection());
+        assert!(StatusCode::IM_A_TEAPOT.is_client_error());
+        assert!(StatusCode::BAD_GATEWAY.is_server_error());
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, 429);
+        assert_eq!(StatusCode::CREATED.to_string(), "201");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::web::models::problem::{Problem, PROBLEM_JSON};
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::request_handler::HandleError;
+use crate::web::util::parsers::request_parser::RequestParseError;
+use crate::web::util::parsers::quality_value_parser;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+const HTML: &str = "text/html";
+const JSON: &str = "application/json";
+
+/// The default not found handler. Serves `src/public/404.html` or a JSON body, depending on what
+/// the client accepts.
+pub fn not_found(req: &Request) -> Response {
+    negotiated_error_response(Some(req), StatusCode::NOT_FOUND)
+}
+
+/// The default method not allowed handler. Serves `src/public/405.html` or a JSON body, depending
+/// on what the client accepts. The `Allow` header is added by the request handler.
+pub fn method_not_allowed(req: &Request) -> Response {
+    negotiated_error_response(Some(req), StatusCode::METHOD_NOT_ALLOWED)
+}
+
+/// The default error handler. Logs the error, and serves `src/public/500.html` or a JSON body,
+/// depending on what the client accepts. Clients that sent nothing before the timeout get a
+/// `408 Request Timeout` instead, malformed requests a `400 Bad Request`, and requests too large
+/// to read a `413 Content Too Large`.
+///
+/// The request is passed in whenever it was parsed, e.g. for too large bodies, invalid paths,
+/// invalid response headers and panicking handlers, so those get problem details if the client
+/// prefers JSON. Errors while reading the request, e.g. a malformed request line, a timeout or an
+/// incomplete body, leave no request and no `Accept` header, so they always get the HTML response.
+///
+/// Arguments:
+///
+/// * `req`: The request, if it got far enough to be parsed.
+/// * `error`: What went wrong.
+pub fn error(req: Option<&Request>, error: &HandleError) -> Response {
+    if let HandleError::RequestParseFailed(RequestParseError::TimedOut) = error {
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("Client sent no request before the timeout");
+        }
+        return negotiated_error_response(req, StatusCode::REQUEST_TIMEOUT);
+    }
+    if let HandleError::RequestParseFailed(parse_error) = error {
+        let status: Option<StatusCode> = match parse_error {
+            RequestParseError::NoMethod | RequestParseError::NoPath | RequestParseError::NoProtocol
+            | RequestParseError::InvalidPath | RequestParseError::IncompleteBody => Some(StatusCode::BAD_REQUEST),
+            RequestParseError::TooLarge => Some(StatusCode::CONTENT_TOO_LARGE),
+            _ => None
+        };
+        if let Some(status) = status {
+            if log::log_enabled!(log::Level::Debug) {
+                log::debug!("Rejected request: {}", parse_error.to_string());
+            }
+            return negotiated_error_response(req, status);
+        }
+    }
+    if log::log_enabled!(log::Level::Error) {
+        log::error!("{:?}", error);
+    }
+    negotiated_error_response(req, StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// It returns `true` if the client would rather have JSON, or problem details, than HTML, judging by
+/// the `Accept` header. Requests without an `Accept` header get HTML.
+///
+/// Arguments:
+///
+/// * `req`: The request to check.
+///
+/// Returns:
+///
+/// A boolean value.
+pub fn prefers_json(req: &Request) -> bool {
+    req.get_header("Accept")
+        .and_then(|accept: &str| quality_value_parser::negotiate(accept, &[HTML, JSON, PROBLEM_JSON]))
+        .is_some_and(|media_type: &str| media_type != HTML)
+}
+
+/// It builds an error response with a body the client can use. Clients that prefer JSON get a
+/// `Problem`, e.g. `{"type":"about:blank","title":"Not Found","status":404}` as
+/// `application/problem+json`, everyone else gets `src/public/{status}.html` if it exists, or an
+/// empty body if it doesn't.
+///
+/// Arguments:
+///
+/// * `req`: The request being answered, if there is one.
+/// * `status`: The HTTP status code.
+///
+/// Returns:
+///
+/// A Response
+pub fn negotiated_error_response(req: Option<&Request>, status: StatusCode) -> Response {
+    if req.is_some_and(prefers_json) {
+        return Problem::new(status).into();
+    }
+    let mut res: Response = Response::new(status);
+    if res.set_body_to_file(&format!("/{}.html", status)).is_err()
+        && log::log_enabled!(log::Level::Debug) {
+        log::debug!("No error page found for status: {}", status);
+    }
+    res
+}
@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::util::enums::http_method_enum::HttpMethod;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+lazy_static::lazy_static! {
+    /// The metrics of the server, recorded by `request_handler::handle`.
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// The route label of requests that matched no route, e.g. static resources and 404s.
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// A latency histogram of one method and route.
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64
+}
+
+/// `Metrics` counts requests and connections, exposed in the Prometheus text format by the route
+/// added with `RouteHandlerContainer::enable_metrics`. Requests are labelled with the registered
+/// route pattern, e.g. "/users/{id}", never the raw path, so the number of series stays bounded.
+///
+/// Properties:
+///
+/// * `requests`: Requests by method, route and status.
+/// * `durations`: Latency histograms by method and route.
+/// * `in_flight`: Requests currently being handled.
+/// * `connections_open`: Connections currently open.
+/// * `connections_total`: Connections accepted since start.
+pub struct Metrics {
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    durations: Mutex<BTreeMap<(String, String), Histogram>>,
+    in_flight: AtomicI64,
+    connections_open: AtomicI64,
+    connections_total: AtomicU64
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// It decrements a gauge when dropped, so it is also decremented when a handler panics.
+pub struct GaugeGuard<'a>(&'a AtomicI64);
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            requests: Mutex::new(BTreeMap::new()),
+            durations: Mutex::new(BTreeMap::new()),
+            in_flight: AtomicI64::new(0),
+            connections_open: AtomicI64::new(0),
+            connections_total: AtomicU64::new(0)
+        }
+    }
+
+    /// It counts an accepted connection, which stays open until the guard is dropped.
+    pub fn connection_opened(&self) -> GaugeGuard<'_> {
+        self.connections_total.fetch_add(1, Ordering::Relaxed);
+        self.connections_open.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(&self.connections_open)
+    }
+
+    /// It counts a request in flight until the guard is dropped.
+    pub fn request_started(&self) -> GaugeGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        GaugeGuard(&self.in_flight)
+    }
+
+    /// It records a finished request.
+    ///
+    /// Arguments:
+    ///
+    /// * `method`: The request method. Unknown methods are recorded as "OTHER".
+    /// * `route`: The registered route pattern, or `UNMATCHED_ROUTE`.
+    /// * `status`: The status of the response.
+    /// * `duration`: How long the request took.
+    pub fn record_request(&self, method: &str, route: &str, status: u16, duration: Duration) {
+        let method: String = match HttpMethod::from_str(method) {
+            Ok(method) => method.to_string(),
+            Err(()) => "OTHER".to_string()
+        };
+        *self.requests.lock().unwrap_or_else(|e| e.into_inner())
+            .entry((method.clone(), route.to_string(), status))
+            .or_insert(0) += 1;
+        let seconds: f64 = duration.as_secs_f64();
+        let mut durations = self.durations.lock().unwrap_or_else(|e| e.into_inner());
+        let histogram: &mut Histogram = durations.entry((method, route.to_string())).or_default();
+        for (bucket, upper_bound) in histogram.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= upper_bound {
+                *bucket += 1;
+            }
+        }
+        histogram.sum += seconds;
+        histogram.count += 1;
+    }
+
+    /// It renders all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out: String = String::new();
+        out.push_str("# HELP http_requests_total Total HTTP requests.\n# TYPE http_requests_total counter\n");
+        for ((method, route, status), count) in self.requests.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = writeln!(out, "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                             method, escape_label(route), status, count);
+        }
+        out.push_str("# HELP http_request_duration_seconds HTTP request latency.\n\
+                      # TYPE http_request_duration_seconds histogram\n");
+        for ((method, route), histogram) in self.durations.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let labels: String = format!("method=\"{}\",route=\"{}\"", method, escape_label(route));
+            for (bucket, upper_bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, upper_bound, bucket);
+            }
+            let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+            let _ = writeln!(out, "http_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum);
+            let _ = writeln!(out, "http_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+        }
+        let _ = write!(out, "# HELP http_requests_in_flight HTTP requests being handled.\n\
+                             # TYPE http_requests_in_flight gauge\nhttp_requests_in_flight {}\n\
+                             # HELP http_connections_open Open connections.\n\
+                             # TYPE http_connections_open gauge\nhttp_connections_open {}\n\
+                             # HELP http_connections_total Accepted connections.\n\
+                             # TYPE http_connections_total counter\nhttp_connections_total {}\n",
+                       self.in_flight.load(Ordering::Relaxed),
+                       self.connections_open.load(Ordering::Relaxed),
+                       self.connections_total.load(Ordering::Relaxed));
+        out
+    }
+}
+
+/// It escapes a label value, as required by the exposition format.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// The handler of the metrics route, added with `RouteHandlerContainer::enable_metrics`.
+pub fn metrics_handler(_req: &Request) -> Response {
+    let mut res: Response = Response::ok();
+    res.add_header("Content-Type", "text/plain; version=0.0.4; charset=utf-8".to_string());
+    res.set_body(METRICS.render());
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use crate::web::request_handling::metrics::Metrics;
+
+    #[test]
+    fn renders_exposition_format() {
+        let metrics: Metrics = Metrics::new();
+        let _connection = metrics.connection_opened();
+        {
+            let _request = metrics.request_started();
+            metrics.record_request("GET", "/users/{id}", 200, Duration::from_millis(30));
+            metrics.record_request("GET", "/users/{id}", 200, Duration::from_millis(3));
+            metrics.record_request("BREW", "unmatched", 405, Duration::from_millis(1));
+        }
+        let rendered: String = metrics.render();
+        assert!(rendered.contains("http_requests_total{method=\"GET\",route=\"/users/{id}\",status=\"200\"} 2\n"));
+        assert!(rendered.contains("http_requests_total{method=\"OTHER\",route=\"unmatched\",status=\"405\"} 1\n"));
+        assert!(rendered.contains("http_request_duration_seconds_bucket{method=\"GET\",route=\"/users/{id}\",le=\"0.005\"} 1\n"));
+        assert!(rendered.contains("http_request_duration_seconds_bucket{method=\"GET\",route=\"/users/{id}\",le=\"0.05\"} 2\n"));
+        assert!(rendered.contains("http_request_duration_seconds_count{method=\"GET\",route=\"/users/{id}\"} 2\n"));
+        assert!(rendered.contains("http_requests_in_flight 0\n"));
+        assert!(rendered.contains("http_connections_open 1\n"));
+        assert!(rendered.contains("http_connections_total 1\n"));
+    }
+}
@@ -1,16 +1,25 @@
-use std::io::Write;
 use std::net::TcpStream;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::str::FromStr;
 use di_ioc_lib::di::ioc_container::IocContainer;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use crate::web::models::request::Request;
 
+use crate::web::middleware::middleware_trait::Middleware;
 use crate::web::models::response::Response;
+use crate::web::models::status_code::StatusCode;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::request_handling::metrics::{METRICS, UNMATCHED_ROUTE};
+use crate::web::request_handling::route::{MatchedRoute, Route};
 use crate::web::request_handling::route_handler_container::RouteHandlerContainer;
+use crate::web::request_handling::static_files::StaticFiles;
+use crate::web::request_handling::timeouts::{Timeouts, Watchdog};
+use crate::web::server::HandlerFunction;
 use crate::web::util::enums::http_method_enum::HttpMethod;
 use crate::web::util::parsers::request_parser;
-use crate::web::util::parsers::request_parser::RequestParseError;
+use crate::web::util::parsers::request_parser::{RequestParseError, Truncated};
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
 // ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
@@ -26,9 +35,12 @@ use crate::web::util::parsers::request_parser::RequestParseError;
 #[derive(Debug)]
 pub enum HandleError {
     AlreadyResolved,
-    HttpStatusNotSet,
     ResolveFailed,
-    UnobtainedMutex
+    UnobtainedMutex,
+    RequestParseFailed(RequestParseError),
+    /// The response had a header with a line break in it, the name of which is given.
+    InvalidHeader(String),
+    HandlerPanicked(String)
 }
 
 /// It takes a `Transaction` and a `Container` and
@@ -38,52 +50,64 @@ pub enum HandleError {
 ///
 /// * `transaction`: The transaction object that is passed through the chain.
 /// * `container`: Arc<Container> - This is the container that holds the route map.
-pub fn handle<'a>(tcp_stream: std::io::Result<TcpStream>, container: Arc<IocContainer>) -> bool {
+pub fn handle(tcp_stream: std::io::Result<TcpStream>, container: Arc<IocContainer>) -> bool {
     let mut unwrapped_stream: TcpStream = tcp_stream.unwrap(); // TODO.
+    let _connection = METRICS.connection_opened();
+
+    // We get a reference to the container containing our mapped routes from the IocContainer.
+    let route_map: &RouteHandlerContainer = container.get_ref()
+        .expect("Failed to get RouteHandlerContainer.");
+    let timeouts: Timeouts = container.get_ref::<Timeouts>().copied().unwrap_or_default();
+    // Timeouts apply to every clone of the stream, since they are set on the socket.
+    let _ = unwrapped_stream.set_read_timeout(Some(timeouts.get_idle()));
+    let _ = unwrapped_stream.set_write_timeout(Some(timeouts.get_write()));
 
     let req: Result<Request, RequestParseError> = request_parser::parse_request(
         unwrapped_stream.try_clone().unwrap(),
-        [0; 1024]
+        &timeouts
     );
 
-    if req.is_err() {
-        let _ = unwrapped_stream.write(
-            Response::new(500, "Internal Server Error")
-                .get_as_u8_vec()
-                .as_slice()
-        );
+    let mut req: Request = match req {
+        Ok(req) => req,
+        Err(e) => {
+            let _ = route_map.error_handler()(None, &HandleError::RequestParseFailed(e))
+                .write_to(&mut unwrapped_stream);
+            return false;
+        }
+    };
+    if req.extension::<Truncated>().is_some() {
+        let error: HandleError = HandleError::RequestParseFailed(RequestParseError::TooLarge);
+        let _ = req.resolve(route_map.error_handler()(Some(&req), &error));
         return false;
     }
 
-    let req: Request = req.unwrap();
-
     if log::log_enabled!(log::Level::Info) {
-        log::info!("Request Received from {}", req.stream().peer_addr().unwrap());
+        log::info!("Request Received from {}", unwrapped_stream.peer_addr().unwrap());
     }
 
     let start_time: Instant = Instant::now();
-    let path: &str = req.request_line_data().path();
-
-    // We get a reference to the container containing our mapped routes from the IocContainer.
-    let route_map: &RouteHandlerContainer = container.get_ref()
-        .expect("Failed to get RouteHandlerContainer.");
-
-    let method: HttpMethod = HttpMethod::from_str(req.request_line_data().method())
-        .expect("Invalid http method");
 
-    //  Here we are matching the requested path to our mapped routes.
-    let res: Response = if let Some(handler) = route_map.get_match(&path, &method) {
-        handler(&req)
-    } else { // We find no match, so we need to rule out static resources, or resolve.
-        if req.request_line_data().method() == HttpMethod::GET.to_string() {
-            let path_bind = req.request_line_data().path().to_owned();
-            rule_out_static_resources(path_bind)
-        } else {
-            Response::not_found()
-        }
+    let watchdog: Option<Watchdog> = match (timeouts.get_handler(), unwrapped_stream.try_clone()) {
+        (Some(deadline), Ok(stream)) => Some(start_watchdog(deadline, stream, &req, route_map)),
+        _ => None
     };
-    let debug_res: Response = res.clone();
-    match req.resolve(res) {
+    let in_flight = METRICS.request_started();
+    let (req, res): (&Request, Response) = dispatch(&mut req, &container);
+    drop(in_flight);
+    let timed_out: bool = watchdog.is_some_and(|watchdog: Watchdog| watchdog.finish());
+    METRICS.record_request(req.request_line_data().method(), req.route_pattern().unwrap_or(UNMATCHED_ROUTE),
+                           if timed_out { 503 } else { res.status().as_u16() }, start_time.elapsed());
+    if timed_out {
+        // The client already got a 503 from the watchdog.
+        return true;
+    }
+    let status: StatusCode = res.status();
+    // A response with an invalid header isn't sent, so the client gets the error handler's instead.
+    let resolved: Result<(), HandleError> = req.resolve(res).or_else(|e: HandleError| match e {
+        HandleError::InvalidHeader(_) => req.resolve(route_map.error_handler()(Some(req), &e)),
+        e => Err(e)
+    });
+    match resolved {
         Err(e) => {
             if log::log_enabled!(log::Level::Error) {
                 log::error!("{:?}", e);
@@ -93,41 +117,372 @@ pub fn handle<'a>(tcp_stream: std::io::Result<TcpStream>, container: Arc<IocCont
             let now: Instant = Instant::now();
             if log::log_enabled!(log::Level::Info) {
                 log::info!("Transaction resolved for: {}, status: {}, path: {}, in: {}ms",
-                    req.stream().peer_addr().unwrap(),
-                    debug_res.status(),
+                    unwrapped_stream.peer_addr().unwrap(),
+                    status,
                     req.request_line_data().path(),
                     now.duration_since(start_time).as_secs_f32() * 1000.0
                 );
             }
-            if log::log_enabled!(log::Level::Debug) { log::debug!("\n{:?}", req); }
+            // Only the request line and body size, headers and bodies may hold credentials.
+            if log::log_enabled!(log::Level::Debug) {
+                log::debug!("{} {} {}, body: {} bytes",
+                    req.request_line_data().method(),
+                    req.request_line_data().path(),
+                    req.request_line_data().protocol(),
+                    req.body().len()
+                );
+            }
         }
     }
     true
 }
 
-/// If the path contains a dot, then try to serve the file
+/// It starts a watchdog answering `503 Service Unavailable` if the request isn't handled before
+/// the deadline. The response is prepared up front, since the request is busy once it's handled.
 ///
 /// Arguments:
 ///
-/// * `transaction`: &mut Transaction
+/// * `deadline`: How long the middleware and the handler may take.
+/// * `stream`: A clone of the connection.
+/// * `req`: The request about to be handled.
+/// * `route_map`: The container holding the routes, to name the route in the warning.
 ///
 /// Returns:
 ///
-/// A boolean value.
-fn rule_out_static_resources<'a>(path: String) -> Response<'a> {
-    let mut res: Response = Response::not_found();
-    if path.contains('.') {
-        match res.set_body_to_file(&path) {
-            Ok(_) => {
-                res.set_ok();
-            },
-            Err(e) => {
-                res.set_not_found();
-                if log::log_enabled!(log::Level::Error) {
-                    log::error!("{}", e);
-                }
-            }
+/// The Watchdog, to be finished when the response is ready.
+fn start_watchdog(deadline: Duration, stream: TcpStream, req: &Request, route_map: &RouteHandlerContainer) -> Watchdog {
+    let method: &str = req.request_line_data().method();
+    let route: &str = HttpMethod::from_str(method).ok()
+        .and_then(|method: HttpMethod| route_map.get_route(req.request_line_data().path(), &method))
+        .map(|route: &Route| route.path())
+        .unwrap_or(UNMATCHED_ROUTE);
+    let mut timeout_response: Response = fallback_handlers::negotiated_error_response(
+        Some(req), StatusCode::SERVICE_UNAVAILABLE);
+    Watchdog::start(deadline, stream, timeout_response.get_as_u8_vec(), format!("{} {}", method, route))
+}
+
+/// It passes the request through the middleware chain, and produces the response to resolve it
+/// with. It doesn't touch the connection, so it can be used without sockets, e.g. with a request
+/// built by `Request::new_without_stream`.
+///
+/// Global `before` middleware runs first, then the request is routed, then the route's `before`
+/// middleware runs, then the handler or a fallback handler, and then all `after` middleware in
+/// reverse order. Requests whose path can't be normalised, e.g. because of a `..` segment, get the
+/// error handler's `400 Bad Request` before any middleware sees them.
+///
+/// Arguments:
+///
+/// * `req`: The request to handle.
+/// * `container`: The container holding the `RouteHandlerContainer`.
+///
+/// Returns:
+///
+/// The request again, as a shared reference for after the middleware is done with it, and the
+/// response.
+pub fn dispatch<'a>(req: &'a mut Request, container: &IocContainer) -> (&'a Request, Response) {
+    // We get a reference to the container containing our mapped routes from the IocContainer.
+    let route_map: &RouteHandlerContainer = container.get_ref()
+        .expect("Failed to get RouteHandlerContainer.");
+    if req.request_line_data().normalized_path().is_none() {
+        let error: HandleError = HandleError::RequestParseFailed(RequestParseError::InvalidPath);
+        let res: Response = route_map.error_handler()(Some(req), &error);
+        return (req, res);
+    }
+    let global_middleware: &Vec<Arc<dyn Middleware>> = route_map.middleware();
+    let (global_ran, mut short_circuit): (usize, Option<Response>) =
+        run_before(global_middleware, req, container);
+
+    let method: Result<HttpMethod, ()> = HttpMethod::from_str(req.request_line_data().method());
+    //  Here we are matching the requested path to our mapped routes.
+    let route: Option<&Route> = match (&short_circuit, &method) {
+        (None, Ok(method)) => route_map.get_route(req.request_line_data().path(), method),
+        _ => None
+    };
+    let mut route_ran: usize = 0;
+    if let (None, Some(route)) = (&short_circuit, route) {
+        req.extensions_mut().insert(MatchedRoute::new(route.path()));
+        (route_ran, short_circuit) = run_before(route.middleware(), req, container);
+    }
+
+    let req: &'a Request = req;
+    let mut res: Response = if let Some(res) = short_circuit {
+        res
+    } else if let Some(route) = route {
+        call_handler(route.handler_function(), req, route_map)
+    } else {
+        match method {
+            Ok(method) => fallback(req, route_map, &method),
+            Err(()) => method_not_allowed(req, route_map)
         }
+    };
+    // Ranges are applied before the after hooks, so they log and check the response being sent.
+    res.apply_range(req);
+
+    if let Some(route) = route {
+        run_after(&route.middleware()[..route_ran], req, &mut res, container);
+    }
+    run_after(&global_middleware[..global_ran], req, &mut res, container);
+    (req, res)
+}
+
+/// It calls `before` on every middleware in order, until one of them returns a response.
+///
+/// Arguments:
+///
+/// * `middleware`: The middleware to run.
+/// * `req`: The request being handled.
+/// * `container`: The container the server was started with.
+///
+/// Returns:
+///
+/// How many middleware ran to completion, and the short-circuit response, if there was one.
+fn run_before(middleware: &[Arc<dyn Middleware>], req: &mut Request, container: &IocContainer)
+    -> (usize, Option<Response>) {
+    for (i, current) in middleware.iter().enumerate() {
+        if let Some(res) = current.before(req, container) {
+            return (i, Some(res));
+        }
+    }
+    (middleware.len(), None)
+}
+
+/// It calls `after` on every middleware in reverse order.
+///
+/// Arguments:
+///
+/// * `middleware`: The middleware to run.
+/// * `req`: The request being handled.
+/// * `res`: The response to post-process.
+/// * `container`: The container the server was started with.
+fn run_after(middleware: &[Arc<dyn Middleware>], req: &Request, res: &mut Response, container: &IocContainer) {
+    for current in middleware.iter().rev() {
+        current.after(req, res, container);
+    }
+}
+
+/// It's called when no route matches the request, and falls back to static resources for `GET`
+/// and `HEAD`, the method not allowed handler, or the not found handler, in that order.
+///
+/// Arguments:
+///
+/// * `req`: The request to route.
+/// * `route_map`: The container holding the routes and fallback handlers.
+/// * `method`: The parsed method of the request.
+///
+/// Returns:
+///
+/// The Response to resolve the request with.
+fn fallback(req: &Request, route_map: &RouteHandlerContainer, method: &HttpMethod) -> Response {
+    let path: &str = req.request_line_data().path();
+    // We find no match, so we need to rule out static resources, or resolve.
+    if *method == HttpMethod::GET || *method == HttpMethod::HEAD {
+        if let Some(res) = rule_out_static_resources(req, route_map) {
+            return res;
+        }
+    }
+    if route_map.allowed_methods(path).is_empty() {
+        call_handler(route_map.not_found_handler(), req, route_map)
+    } else {
+        method_not_allowed(req, route_map)
+    }
+}
+
+/// It calls the method not allowed handler, and adds an `Allow` header listing the methods that
+/// do have a route for the path, unless the handler set one itself.
+///
+/// Arguments:
+///
+/// * `req`: The request with the unsupported method.
+/// * `route_map`: The container holding the routes and fallback handlers.
+///
+/// Returns:
+///
+/// A Response
+fn method_not_allowed(req: &Request, route_map: &RouteHandlerContainer) -> Response {
+    let mut allowed_methods: Vec<String> = route_map
+        .allowed_methods(req.request_line_data().path())
+        .iter()
+        .map(|method: &&HttpMethod| method.to_string())
+        .collect();
+    allowed_methods.sort();
+    let mut res: Response = call_handler(route_map.method_not_allowed_handler(), req, route_map);
+    if !res.headers().contains_key("Allow") {
+        res.add_header("Allow", allowed_methods.join(", "));
     }
     res
-}
\ No newline at end of file
+}
+
+/// It calls a handler, and turns a panic into a call to the error handler, so the client gets a
+/// response instead of a closed connection.
+///
+/// Arguments:
+///
+/// * `handler`: The handler to call.
+/// * `req`: The request to pass to the handler.
+/// * `route_map`: The container holding the error handler.
+///
+/// Returns:
+///
+/// The Response of the handler, or of the error handler if it panicked.
+fn call_handler(handler: HandlerFunction, req: &Request, route_map: &RouteHandlerContainer)
+    -> Response {
+    match panic::catch_unwind(AssertUnwindSafe(|| handler(req))) {
+        Ok(res) => res,
+        Err(payload) => {
+            let message: String = payload.downcast_ref::<&str>()
+                .map(|s: &&str| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            route_map.error_handler()(Some(req), &HandleError::HandlerPanicked(message))
+        }
+    }
+}
+
+/// It tries to serve the request from the mounted static files, longest prefix first.
+///
+/// Arguments:
+///
+/// * `req`: The GET or HEAD request no route matches.
+/// * `route_map`: The container holding the mounted static files.
+///
+/// Returns:
+///
+/// The Response serving the file or directory, or `None` if no mount has one for the path.
+fn rule_out_static_resources(req: &Request, route_map: &RouteHandlerContainer) -> Option<Response> {
+    route_map.static_files()
+        .iter()
+        .find_map(|static_files: &StaticFiles| static_files.serve(req))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+    use di_ioc_lib::di::ioc_container::IocContainer;
+    use crate::web::middleware::middleware_trait::Middleware;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::models::status_code::StatusCode;
+    use crate::web::request_handling::request_handler::dispatch;
+    use crate::web::request_handling::route_handler_container::RouteHandlerContainer;
+    use crate::web::request_handling::static_files::StaticFiles;
+    use crate::web::util::enums::http_method_enum::HttpMethod;
+
+    struct Recorder {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+        short_circuit: bool
+    }
+
+    impl Middleware for Recorder {
+        fn before(&self, _req: &mut Request, _container: &IocContainer) -> Option<Response> {
+            self.log.lock().unwrap().push(format!("before {}", self.name));
+            if self.short_circuit { Some(Response::new(StatusCode::UNAUTHORIZED)) } else { None }
+        }
+
+        fn after(&self, _req: &Request, res: &mut Response, _container: &IocContainer) {
+            self.log.lock().unwrap().push(format!("after {}", self.name));
+            res.add_header("X-After", self.name.to_string());
+        }
+    }
+
+    fn handler(_req: &Request) -> Response {
+        Response::ok()
+    }
+
+    fn container(log: &Arc<Mutex<Vec<String>>>, short_circuit_route: bool) -> IocContainer {
+        let recorder = |name: &'static str, short_circuit: bool| Recorder {
+            name, log: Arc::clone(log), short_circuit
+        };
+        let mut rhc: RouteHandlerContainer = RouteHandlerContainer::new();
+        rhc.insert("/test", handler, HttpMethod::GET);
+        rhc.add_middleware(recorder("global 1", false));
+        rhc.add_middleware(recorder("global 2", false));
+        rhc.add_route_middleware("/test", HttpMethod::GET, recorder("route", short_circuit_route));
+        let mut container: IocContainer = IocContainer::default();
+        container.install_reference_provider(Arc::new(rhc));
+        container
+    }
+
+    #[test]
+    fn runs_middleware_in_order() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let container: IocContainer = container(&log, false);
+        let mut req: Request = Request::new_without_stream(b"GET /test HTTP/1.1", b"").unwrap();
+        let (_, res) = dispatch(&mut req, &container);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers().get("X-After").unwrap(), "global 1");
+        assert_eq!(*log.lock().unwrap(), vec!["before global 1", "before global 2", "before route",
+                                              "after route", "after global 2", "after global 1"]);
+    }
+
+    #[test]
+    fn short_circuits() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let container: IocContainer = container(&log, true);
+        let mut req: Request = Request::new_without_stream(b"GET /test HTTP/1.1", b"").unwrap();
+        let (_, res) = dispatch(&mut req, &container);
+        assert_eq!(res.status(), 401);
+        assert_eq!(*log.lock().unwrap(), vec!["before global 1", "before global 2", "before route",
+                                              "after global 2", "after global 1"]);
+    }
+
+    #[test]
+    fn global_middleware_wraps_fallbacks() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let container: IocContainer = container(&log, false);
+        let mut req: Request = Request::new_without_stream(b"POST /test HTTP/1.1", b"").unwrap();
+        let (_, res) = dispatch(&mut req, &container);
+        assert_eq!(res.status(), 405);
+        assert_eq!(res.headers().get("Allow").unwrap(), "GET, HEAD");
+        assert_eq!(log.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn group_middleware_covers_every_spelling_of_a_path() {
+        let root: std::path::PathBuf = std::env::temp_dir().join(format!("group-static-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("admin")).unwrap();
+        std::fs::write(root.join("admin/secret.txt"), "secret").unwrap();
+        std::fs::write(root.join("public.txt"), "public").unwrap();
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut rhc: RouteHandlerContainer = RouteHandlerContainer::new();
+        rhc.add_group_middleware("/admin", Recorder { name: "deny", log: Arc::clone(&log), short_circuit: true });
+        rhc.add_static_files(StaticFiles::new(&root).unwrap());
+        let mut container: IocContainer = IocContainer::default();
+        container.install_reference_provider(Arc::new(rhc));
+
+        for (path, status) in [("/admin/secret.txt", 401), ("//admin/secret.txt", 401), ("/./admin/secret.txt", 401),
+                               ("/%61dmin/secret.txt", 401), ("/admin/../admin/secret.txt", 400), ("//%70ublic.txt", 200)] {
+            let mut req: Request = Request::new_without_stream(format!("GET {} HTTP/1.1", path).as_bytes(), b"").unwrap();
+            let (_, res) = dispatch(&mut req, &container);
+            assert_eq!(res.status(), status, "{}", path);
+        }
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn answers_head_like_get() {
+        let root: std::path::PathBuf = std::env::temp_dir().join(format!("head-static-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("app.css"), "body {}").unwrap();
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let container: IocContainer = container(&log, false);
+        let mut rhc: RouteHandlerContainer = RouteHandlerContainer::new();
+        rhc.insert("/test", handler, HttpMethod::GET);
+        rhc.add_static_files(StaticFiles::new(&root).unwrap());
+        let mut static_container: IocContainer = IocContainer::default();
+        static_container.install_reference_provider(Arc::new(rhc));
+
+        let mut req: Request = Request::new_without_stream(b"HEAD /test HTTP/1.1", b"").unwrap();
+        let (req, res) = dispatch(&mut req, &container);
+        assert_eq!(res.status(), 200);
+        assert_eq!(req.route_pattern(), Some("/test"));
+        assert_eq!(log.lock().unwrap().len(), 6);
+        let mut req: Request = Request::new_without_stream(b"HEAD /app.css HTTP/1.1", b"").unwrap();
+        let (_, res) = dispatch(&mut req, &static_container);
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.body_length(), Some(7));
+        let mut req: Request = Request::new_without_stream(b"HEAD /missing.css HTTP/1.1", b"").unwrap();
+        assert_eq!(dispatch(&mut req, &static_container).1.status(), 404);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::server::HandlerFunction;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `MatchedRoute` is attached to a request when it matches a route, holding the path pattern the
+/// route was inserted with, e.g. "/users/{id}" for "/users/42".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRoute(String);
+
+impl MatchedRoute {
+    pub fn new(pattern: &str) -> Self {
+        Self(pattern.to_string())
+    }
+    pub fn pattern(&self) -> &str {
+        &self.0
+    }
+}
+
+/// `Route` is a handler registered in the `RouteHandlerContainer`, together with the middleware
+/// that only applies to it.
+///
+/// Properties:
+///
+/// * `path`: The path pattern the route was inserted with, e.g. "/users/{id}".
+/// * `handler_function`: The handler of the route.
+/// * `middleware`: Middleware wrapping the handler, in the order it was added.
+#[derive(Clone)]
+pub struct Route {
+    path: String,
+    handler_function: HandlerFunction,
+    middleware: Vec<Arc<dyn Middleware>>
+}
+
+impl Route {
+    pub fn new(path: &str, handler_function: HandlerFunction) -> Self {
+        Self { path: path.to_string(), handler_function, middleware: Vec::new() }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn handler_function(&self) -> HandlerFunction {
+        self.handler_function
+    }
+    pub fn middleware(&self) -> &Vec<Arc<dyn Middleware>> {
+        &self.middleware
+    }
+
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middleware.push(middleware);
+    }
+}
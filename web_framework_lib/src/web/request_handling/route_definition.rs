@@ -0,0 +1,52 @@
+use crate::web::server::HandlerFunction;
+use crate::web::util::enums::http_method_enum::HttpMethod;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `RouteDefinition` is a route declared with one of the route attribute macros, e.g.
+/// `#[get("/users/{id}")]`. Every annotated handler submits one of these at compile time, and
+/// `RouteHandlerContainer::insert_annotated_routes` inserts all of them.
+///
+/// Properties:
+///
+/// * `path`: The path pattern, e.g. "/users/{id}".
+/// * `handler_function`: The annotated handler.
+/// * `method`: The HTTP method the handler answers to.
+pub struct RouteDefinition {
+    path: &'static str,
+    handler_function: HandlerFunction,
+    method: HttpMethod
+}
+
+inventory::collect!(RouteDefinition);
+
+impl RouteDefinition {
+    /// `new` is `const`, so the route attribute macros can submit definitions from a static.
+    pub const fn new(path: &'static str, handler_function: HandlerFunction, method: HttpMethod) -> Self {
+        Self { path, handler_function, method }
+    }
+
+    /// It returns every route declared with a route attribute macro, in no particular order.
+    pub fn all() -> impl Iterator<Item = &'static RouteDefinition> {
+        inventory::iter::<RouteDefinition>.into_iter()
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+    pub fn handler_function(&self) -> HandlerFunction {
+        self.handler_function
+    }
+    pub fn method(&self) -> &HttpMethod {
+        &self.method
+    }
+}
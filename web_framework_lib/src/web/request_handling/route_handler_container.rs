@@ -1,8 +1,15 @@
 use di_ioc_lib::di::providable_trait::Providable;
 use std::collections::HashMap;
 use std::ops::Add;
+use std::sync::Arc;
 use regex::{Error, Regex};
-use crate::web::server::HandlerFunction;
+use crate::web::middleware::group_middleware::GroupMiddleware;
+use crate::web::middleware::middleware_trait::Middleware;
+use crate::web::request_handling::{fallback_handlers, metrics};
+use crate::web::request_handling::route::Route;
+use crate::web::request_handling::route_definition::RouteDefinition;
+use crate::web::request_handling::static_files::{StaticFiles, DEFAULT_ROOT};
+use crate::web::server::{ErrorHandlerFunction, HandlerFunction};
 use crate::web::util::enums::http_method_enum::HttpMethod;
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
@@ -21,31 +28,76 @@ use crate::web::util::enums::http_method_enum::HttpMethod;
 ///
 /// Properties:
 ///
-/// * `map`: This is a HashMap that will store the regular expression and the handler function.
+/// * `map`: This is a HashMap that will store the regular expression and the route.
+/// * `middleware`: Global middleware, wrapping every request in the order it was added.
+/// * `static_files`: The mounted static files, longest prefix first. `src/public` at "/" by default.
+/// * `not_found_handler`: Called when no route or static resource matches the request.
+/// * `method_not_allowed_handler`: Called when the path matches a route, but not for this method.
+/// * `error_handler`: Called when the request can't be parsed, or when a handler panics.
 pub struct RouteHandlerContainer {
-    method_map: HashMap<HttpMethod, HashMap<String, HandlerFunction>>,
+    method_map: HashMap<HttpMethod, HashMap<String, Route>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    static_files: Vec<StaticFiles>,
+    not_found_handler: HandlerFunction,
+    method_not_allowed_handler: HandlerFunction,
+    error_handler: ErrorHandlerFunction,
 }
 
 impl Providable for RouteHandlerContainer { }
 
+impl Default for RouteHandlerContainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RouteHandlerContainer {
     pub fn new() -> Self {
-        let mut map: HashMap<HttpMethod, HashMap<String, HandlerFunction>> = HashMap::new();
-        map.insert(HttpMethod::GET, HashMap::new());
-        map.insert(HttpMethod::POST, HashMap::new());
-        map.insert(HttpMethod::PUT, HashMap::new());
-        map.insert(HttpMethod::DELETE, HashMap::new());
-        Self { method_map: map }
+        let mut map: HashMap<HttpMethod, HashMap<String, Route>> = HashMap::new();
+        for method in HttpMethod::ALL {
+            map.insert(method, HashMap::new());
+        }
+        Self {
+            method_map: map,
+            middleware: Vec::new(),
+            static_files: StaticFiles::new(DEFAULT_ROOT).into_iter().collect(),
+            not_found_handler: fallback_handlers::not_found,
+            method_not_allowed_handler: fallback_handlers::method_not_allowed,
+            error_handler: fallback_handlers::error
+        }
     }
 
     /// "/cars/{car_id}/wow/"
     /// "/cars/2/wow/" maybe split by slashes and match them?
     pub fn get_match(&self, path: &str, method: &HttpMethod) -> Option<HandlerFunction> {
-        let path_map: Option<&HashMap<String, HandlerFunction>> = self.method_map.get(&method);
+        self.get_route(path, method).map(|route: &Route| route.handler_function())
+    }
+
+    /// It finds the route matching the path and method, like `get_match`, but returns the whole
+    /// route including its middleware. `HEAD` requests without a `HEAD` route get the `GET` route,
+    /// whose body isn't sent.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The request path.
+    /// * `method`: The request method.
+    ///
+    /// Returns:
+    ///
+    /// A reference to the matching Route.
+    pub fn get_route(&self, path: &str, method: &HttpMethod) -> Option<&Route> {
+        match self.find_route(path, method) {
+            None if *method == HttpMethod::HEAD => self.find_route(path, &HttpMethod::GET),
+            route => route
+        }
+    }
+
+    fn find_route(&self, path: &str, method: &HttpMethod) -> Option<&Route> {
+        let path_map: Option<&HashMap<String, Route>> = self.method_map.get(method);
         if path_map.is_none() {
             return None;
         }
-        let path_map: &HashMap<String, HandlerFunction> =
+        let path_map: &HashMap<String, Route> =
             path_map.expect("Failed to get path_map.");
         path_map.iter().find(|(regex_str, _)| {
             let reg_match_result: Result<bool, Error> = Regex
@@ -63,56 +115,222 @@ impl RouteHandlerContainer {
                 },
                 Ok(t) => { t }
             };
-        }).map(|(_, h): (_, &HandlerFunction)| *h)
+        }).map(|(_, route): (_, &Route)| route)
+    }
+
+    /// It adds global middleware, which wraps every request, including the ones answered by the
+    /// fallback handlers or with static resources. Middleware runs in the order it was added.
+    ///
+    /// Arguments:
+    ///
+    /// * `middleware`: The middleware to add.
+    pub fn add_middleware<M: Middleware + 'static>(&mut self, middleware: M) {
+        self.middleware.push(Arc::new(middleware));
+    }
+
+    /// It adds middleware to a single, already inserted route. Route middleware runs after the
+    /// global middleware, in the order it was added.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path pattern the route was inserted with, e.g. "/users/{id}".
+    /// * `method`: The method the route was inserted with.
+    /// * `middleware`: The middleware to add.
+    ///
+    /// Returns:
+    ///
+    /// `false` if there is no such route.
+    pub fn add_route_middleware<M: Middleware + 'static>(&mut self, path: &str, method: HttpMethod, middleware: M) -> bool {
+        self.method_map.get_mut(&method)
+            .and_then(|the_methods_map: &mut HashMap<String, Route>| {
+                the_methods_map.get_mut(&Self::path_to_regex(path))
+            })
+            .map(|route: &mut Route| route.add_middleware(Arc::new(middleware)))
+            .is_some()
+    }
+
+    /// It adds middleware to a group of routes sharing a path prefix, e.g. "/admin" for
+    /// "/admin/users" and "/admin/settings". It runs with the global middleware, in the order it
+    /// was added, so it also covers routes inserted later.
+    ///
+    /// Arguments:
+    ///
+    /// * `prefix`: The path prefix of the group.
+    /// * `middleware`: The middleware to add.
+    pub fn add_group_middleware<M: Middleware + 'static>(&mut self, prefix: &str, middleware: M) {
+        self.middleware.push(Arc::new(GroupMiddleware::new(prefix, Arc::new(middleware))));
+    }
+
+    /// It exposes the metrics of the server in the Prometheus text format, with a GET route that
+    /// can be protected like any other, e.g. with `add_route_middleware`.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path of the route, e.g. "/metrics".
+    pub fn enable_metrics(&mut self, path: &str) {
+        self.insert(path, metrics::metrics_handler, HttpMethod::GET);
+    }
+
+    pub fn middleware(&self) -> &Vec<Arc<dyn Middleware>> {
+        &self.middleware
+    }
+
+    /// It mounts static files at their prefix, for GET requests no route matches. A mount at the
+    /// same prefix is replaced, so `StaticFiles::new("/var/www")?` replaces the default public
+    /// folder, and the longest matching prefix is tried first.
+    ///
+    /// Arguments:
+    ///
+    /// * `static_files`: The files to serve, e.g. `StaticFiles::new("assets")?.prefix("/assets")`.
+    pub fn add_static_files(&mut self, static_files: StaticFiles) {
+        self.static_files.retain(|mounted: &StaticFiles| mounted.get_prefix() != static_files.get_prefix());
+        self.static_files.push(static_files);
+        self.static_files.sort_by_key(|mounted: &StaticFiles| std::cmp::Reverse(mounted.get_prefix().len()));
+    }
+
+    pub fn static_files(&self) -> &Vec<StaticFiles> {
+        &self.static_files
+    }
+
+    /// It inserts every handler annotated with a route attribute, e.g. `#[get("/users/{id}")]`,
+    /// so the routes don't have to be listed by hand.
+    /// ```
+    /// # use web_framework_lib::web::models::request::Request;
+    /// # use web_framework_lib::web::models::response::Response;
+    /// # use web_framework_lib::web::request_handling::route_handler_container::RouteHandlerContainer;
+    /// # use web_framework_lib::web::util::enums::http_method_enum::HttpMethod;
+    /// #[web_framework_lib::get("/users/{id}")]
+    /// fn user(_req: &Request) -> Response { Response::ok() }
+    ///
+    /// # fn main() {
+    /// let mut rhc = RouteHandlerContainer::new();
+    /// rhc.insert_annotated_routes();
+    /// assert!(rhc.get_match("/users/1", &HttpMethod::GET).is_some());
+    /// # }
+    /// ```
+    ///
+    /// Malformed patterns are compile errors:
+    /// ```compile_fail
+    /// # use web_framework_lib::web::models::request::Request;
+    /// # use web_framework_lib::web::models::response::Response;
+    /// #[web_framework_lib::get("/users/{id")]
+    /// fn user(_req: &Request) -> Response { Response::ok() }
+    /// # fn main() {}
+    /// ```
+    pub fn insert_annotated_routes(&mut self) {
+        for route_definition in RouteDefinition::all() {
+            self.insert(route_definition.path(),
+                        route_definition.handler_function(),
+                        *route_definition.method());
+        }
+    }
+
+    /// It returns every method that has a route matching the path. Used to tell a 404 from a 405,
+    /// and to fill the `Allow` header of the latter.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The request path.
+    ///
+    /// Returns:
+    ///
+    /// A Vec<&HttpMethod>, empty if nothing matches the path.
+    pub fn allowed_methods(&self, path: &str) -> Vec<&HttpMethod> {
+        self.method_map.keys()
+            .filter(|method: &&HttpMethod| self.get_match(path, method).is_some())
+            .collect()
+    }
+
+    pub fn not_found_handler(&self) -> HandlerFunction {
+        self.not_found_handler
+    }
+    pub fn method_not_allowed_handler(&self) -> HandlerFunction {
+        self.method_not_allowed_handler
+    }
+    pub fn error_handler(&self) -> ErrorHandlerFunction {
+        self.error_handler
+    }
+
+    /// Replaces the handler used when nothing matches the request.
+    pub fn set_not_found_handler(&mut self, not_found_handler: HandlerFunction) {
+        self.not_found_handler = not_found_handler;
+    }
+    /// Replaces the handler used when the path matches, but the method doesn't. The `Allow` header
+    /// is added to its response automatically.
+    pub fn set_method_not_allowed_handler(&mut self, method_not_allowed_handler: HandlerFunction) {
+        self.method_not_allowed_handler = method_not_allowed_handler;
+    }
+    /// Replaces the handler used when the request can't be parsed, or when a handler panics.
+    pub fn set_error_handler(&mut self, error_handler: ErrorHandlerFunction) {
+        self.error_handler = error_handler;
     }
 
     /// It takes a string and a function, and inserts the function into a hashmap, where the key is a
     /// regex pattern that matches the string
     /// e.g
     /// ```
+    /// # use web_framework_lib::web::models::request::Request;
+    /// # use web_framework_lib::web::models::response::Response;
+    /// # use web_framework_lib::web::request_handling::route_handler_container::RouteHandlerContainer;
+    /// # use web_framework_lib::web::util::enums::http_method_enum::HttpMethod;
+    /// # fn dummy(_req: &Request) -> Response { Response::ok() }
     /// let mut rhc = RouteHandlerContainer::new();
-    /// rhc.insert("/hey/test", dummy);
-    /// rhc.insert("/hey/{param}/test", dummy);
+    /// rhc.insert("/hey/test", dummy, HttpMethod::GET);
+    /// rhc.insert("/hey/{param}/test", dummy, HttpMethod::GET);
     /// ```
     ///
     /// THREAD SAFE
     ///
     /// The string within {} does not matter, it's primarily for semantics.
-    /// Any path cell "/cell/" that contains "{ }" will be replaced with ".{1,}"
+    /// Any path cell "/cell/" that contains "{ }" will be replaced with ".{1,}", everything else
+    /// matches literally, e.g. the `.` in "/files/a.txt".
     /// Arguments:
     ///
     /// * `k`: &str, v: HandlerFunction
     /// * `v`: HandlerFunction
     pub fn insert(&mut self, path: &str, handler_function: HandlerFunction, method: HttpMethod) {
-        let mut k: String = String::from(path);
-        let mut closed_curly_brackets_pos_vec: Vec<usize> = Vec::new();
-        let mut open_curly_brackets_pos_vec: Vec<usize> = Vec::new();
-        for (i, c) in k.chars().enumerate() {
-            if c == '{' {
-                open_curly_brackets_pos_vec.push(i);
-            } else if c == '}' {
-                closed_curly_brackets_pos_vec.push(i);
-            }
-        }
-        if open_curly_brackets_pos_vec.len() != closed_curly_brackets_pos_vec.len() {
+        let k: String = Self::path_to_regex(path);
+        let _ = self.method_map.get_mut(&method)
+            .map(|the_methods_map: &mut HashMap<String, Route>| {
+                the_methods_map.insert(k, Route::new(path, handler_function));
+            });
+    }
+
+    /// It turns a path pattern into the regex pattern it's stored under.
+    /// Any path cell "/cell/" that contains "{ }" will be replaced with ".{1,}", the rest is escaped,
+    /// so characters like `.` or `(` only match themselves.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The path pattern, e.g. "/hey/{param}/test".
+    ///
+    /// Returns:
+    ///
+    /// A String, e.g. "^/hey/.{1,}/test$".
+    fn path_to_regex(path: &str) -> String {
+        let open_count: usize = path.matches('{').count();
+        let closed_count: usize = path.matches('}').count();
+        if open_count != closed_count {
             if log::log_enabled!(log::Level::Error) {
-                log::error!("open_curly_pos_vec.len(): {}, closed_curly_brackets_pos_vec.len(): {}, path: {}",
-                    open_curly_brackets_pos_vec.len(),
-                    closed_curly_brackets_pos_vec.len(),
-                    k
+                log::error!("open curly brackets: {}, closed curly brackets: {}, path: {}",
+                    open_count,
+                    closed_count,
+                    path
                 );
             }
-            panic!("open_curly_brackets_pos_vec.len() != closed_curly_brackets_pos_vec.len()");
+            panic!("open curly brackets count != closed curly brackets count");
         }
-        for (open, closed)
-        in open_curly_brackets_pos_vec.iter().zip(closed_curly_brackets_pos_vec) {
-            k.replace_range(open..&(closed+1), ".{1,}");
+        let mut k: String = String::from("^");
+        let mut rest: &str = path;
+        while let Some((literal, variable)) = rest.split_once('{') {
+            let (_, after) = variable.split_once('}')
+                .unwrap_or_else(|| panic!("unclosed curly bracket in path: {}", path));
+            k.push_str(&regex::escape(literal));
+            k.push_str(".{1,}");
+            rest = after;
         }
-        k = String::from("^").add(&k.add("$"));
-        let _ = self.method_map.get_mut(&method)
-            .map(|the_methods_map: &mut HashMap<String, HandlerFunction>| {
-                the_methods_map.insert(k, handler_function);
-            });
+        k.push_str(&regex::escape(rest));
+        k.add("$")
     }
 }
 
@@ -123,14 +341,40 @@ mod test {
     use crate::web::request_handling::route_handler_container::RouteHandlerContainer;
     use crate::web::util::enums::http_method_enum::HttpMethod;
 
-    fn dummy<'a>(_t: &Request) -> Response<'a> {
+    fn dummy(_t: &Request) -> Response {
         Response::not_found()
     }
 
+    #[crate::get("/annotated/{param}")]
+    fn annotated_dummy(_t: &Request) -> Response {
+        Response::ok()
+    }
+
     #[test]
     fn test() {
         let mut rhc = RouteHandlerContainer::new();
         rhc.insert("/hey/test", dummy, HttpMethod::GET);
         rhc.insert("/hey/{param}/test", dummy, HttpMethod::GET);
     }
+
+    #[test]
+    fn matches_literal_parts_literally() {
+        let mut rhc = RouteHandlerContainer::new();
+        rhc.insert("/files/a.txt", dummy, HttpMethod::GET);
+        rhc.insert("/a(", dummy, HttpMethod::GET);
+        rhc.insert("/files/{name}.txt", dummy, HttpMethod::GET);
+        assert!(rhc.get_match("/files/a.txt", &HttpMethod::GET).is_some());
+        assert!(rhc.get_match("/a(", &HttpMethod::GET).is_some());
+        assert!(rhc.get_match("/files/b.txt", &HttpMethod::GET).is_some());
+        assert!(rhc.get_match("/files/aXtxt", &HttpMethod::GET).is_none());
+        assert!(rhc.get_match("/files/bXtxt", &HttpMethod::GET).is_none());
+    }
+
+    #[test]
+    fn inserts_annotated_routes() {
+        let mut rhc = RouteHandlerContainer::new();
+        rhc.insert_annotated_routes();
+        assert!(rhc.get_match("/annotated/1", &HttpMethod::GET).is_some());
+        assert!(rhc.get_match("/annotated/1", &HttpMethod::POST).is_none());
+    }
 }
@@ -0,0 +1,456 @@
+use std::fs;
+use std::fs::{DirEntry, Metadata};
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::web::models::request::Request;
+use crate::web::models::response::Response;
+use crate::web::request_handling::fallback_handlers;
+use crate::web::util::encoders::{html_encoder, url_encoder};
+use crate::web::util::enums::redirect_kind::RedirectKind;
+use crate::web::util::parsers::http_date_parser::format_http_date;
+use crate::web::util::parsers::json_parser::{escape_json_string, JsonObject, JsonVariant};
+use crate::web::util::parsers::path_parser::normalize_path;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The public folder, relative to the working directory, served at "/" unless configured otherwise.
+pub const DEFAULT_ROOT: &str = "src/public";
+
+/// `StaticFiles` serves the files below a root directory at a URL prefix, e.g. the files in
+/// "/var/www/assets" at "/assets". Mount it with `RouteHandlerContainer::add_static_files`.
+///
+/// The root is canonicalised when it's created, so it doesn't depend on the working directory
+/// afterwards. Request paths are matched after `normalize_path`, the same way group middleware
+/// matches them, so they are percent-decoded before they are checked. Paths with `..` segments,
+/// backslashes or NUL bytes are refused, as are files that only lie inside the root through a
+/// symlink pointing out of it.
+///
+/// A request for a directory, e.g. "/docs/", gets its index file. Without the trailing slash it's
+/// redirected to the path with one first, so relative links in the index file work. If the
+/// directory has no index file, it can be answered with a listing of its entries, as HTML or
+/// as JSON for clients that prefer it.
+///
+/// Properties:
+///
+/// * `root`: The canonical path of the directory the files are served from.
+/// * `prefix`: The URL prefix the files are served at, without a trailing slash. "/" by default.
+/// * `index_files`: The files served for a directory, the first that exists. "index.html" by default.
+/// * `listing`: Whether directories without an index file are listed. Off by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticFiles {
+    root: PathBuf,
+    prefix: String,
+    index_files: Vec<String>,
+    listing: bool
+}
+
+/// `ListingEntry` is a file or directory shown in a directory listing.
+///
+/// Properties:
+///
+/// * `name`: The name of the entry.
+/// * `directory`: Whether the entry is a directory.
+/// * `size`: The size of a file in bytes, 0 for directories.
+/// * `modified`: When the entry was last modified, if the platform knows.
+struct ListingEntry {
+    name: String,
+    directory: bool,
+    size: u64,
+    modified: Option<SystemTime>
+}
+
+impl StaticFiles {
+    /// It creates a service for the files below `root`, served at "/".
+    ///
+    /// Arguments:
+    ///
+    /// * `root`: The directory to serve, absolute or relative to the working directory.
+    ///
+    /// Returns:
+    ///
+    /// The StaticFiles, or an error if the root doesn't exist or isn't a directory.
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, Error> {
+        let root: PathBuf = fs::canonicalize(root)?;
+        if !root.is_dir() {
+            return Err(Error::new(ErrorKind::InvalidInput, format!("Not a directory: {}", root.display())));
+        }
+        Ok(Self { root, prefix: "/".to_string(), index_files: vec!["index.html".to_string()], listing: false })
+    }
+
+    /// The prefix is matched by whole segments, so "/assets" serves "/assets/app.js", but not
+    /// "/assets2/app.js".
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        let prefix: &str = prefix.trim_end_matches('/');
+        self.prefix = if prefix.starts_with('/') { prefix.to_string() } else { format!("/{}", prefix) };
+        self
+    }
+
+    /// An empty list turns index files off.
+    pub fn index_files(mut self, index_files: &[&str]) -> Self {
+        self.index_files = index_files.iter().map(|index_file: &&str| index_file.to_string()).collect();
+        self
+    }
+
+    /// Listings show every entry except hidden ones, whose names start with a dot, and symlinks
+    /// pointing out of the root.
+    pub fn listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+    pub fn get_prefix(&self) -> &str {
+        &self.prefix
+    }
+    pub fn get_index_files(&self) -> &Vec<String> {
+        &self.index_files
+    }
+    pub fn get_listing(&self) -> bool {
+        self.listing
+    }
+
+    /// It finds the file a request path points to. A directory path ending with a slash points
+    /// to its index file.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The request path, still percent-encoded, e.g. "/assets/logo%20dark.svg".
+    ///
+    /// Returns:
+    ///
+    /// The canonical path of the file, or `None` if the path isn't below the prefix, tries to
+    /// leave the root, or doesn't point to a file.
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let path: String = normalize_path(path)?;
+        let target: PathBuf = self.locate(&path)?;
+        if target.is_file() {
+            return Some(target);
+        }
+        path.ends_with('/').then(|| self.index_file(&target)).flatten()
+    }
+
+    /// It answers a GET or HEAD request for a file or directory below the root: with the file, the index
+    /// file of a directory, a redirect adding the missing slash to a directory path, or a listing.
+    /// Files are sent with `Content-Type`, `ETag`, `Last-Modified` and `Accept-Ranges` like
+    /// `Response::set_body_to_file`.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request, whose normalised path is served.
+    ///
+    /// Returns:
+    ///
+    /// The Response, or `None` if there is nothing to serve for the path.
+    pub fn serve(&self, req: &Request) -> Option<Response> {
+        let path: &str = req.request_line_data().normalized_path()?;
+        let target: PathBuf = self.locate(path)?;
+        if target.is_file() {
+            return serve_file(&target);
+        }
+        let index_file: Option<PathBuf> = self.index_file(&target);
+        if index_file.is_none() && !self.listing {
+            return None;
+        }
+        if !path.ends_with('/') {
+            // The normalised path has no empty segments, so "//example.com" can't become a redirect to another host.
+            let location: String = path.split('/')
+                .map(url_encoder::percent_encode)
+                .collect::<Vec<String>>()
+                .join("/");
+            return Some(Response::redirect(&format!("{}/", location), RedirectKind::MovedPermanently));
+        }
+        match index_file {
+            Some(index_file) => serve_file(&index_file),
+            None => self.list(req, path, &target)
+        }
+    }
+
+    /// It finds the file or directory a request path points to, after checking that the path
+    /// stays inside the root.
+    ///
+    /// Arguments:
+    ///
+    /// * `path`: The normalised request path.
+    ///
+    /// Returns:
+    ///
+    /// The canonical path, or `None` if the path isn't below the prefix, tries to leave the root,
+    /// or doesn't exist.
+    fn locate(&self, path: &str) -> Option<PathBuf> {
+        let relative: &str = match path.strip_prefix(self.prefix.trim_end_matches('/')) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ => return None
+        };
+        let mut target: PathBuf = self.root.clone();
+        for segment in relative.split('/').filter(|segment: &&str| !segment.is_empty() && *segment != ".") {
+            if segment == ".." || segment.contains(['\\', '\0']) {
+                return None;
+            }
+            target.push(segment);
+        }
+        // Symlinks are resolved, so a link pointing out of the root is caught here.
+        self.inside_root(&target)
+    }
+
+    fn inside_root(&self, path: &Path) -> Option<PathBuf> {
+        fs::canonicalize(path).ok().filter(|path: &PathBuf| path.starts_with(&self.root))
+    }
+
+    fn index_file(&self, directory: &Path) -> Option<PathBuf> {
+        self.index_files.iter()
+            .filter_map(|index_file: &String| self.inside_root(&directory.join(index_file)))
+            .find(|index_file: &PathBuf| index_file.is_file())
+    }
+
+    /// It lists a directory, as JSON if the client prefers it, otherwise as HTML.
+    ///
+    /// Arguments:
+    ///
+    /// * `req`: The request for the directory.
+    /// * `path`: The normalised request path, ending with a slash.
+    /// * `directory`: The canonical path of the directory.
+    ///
+    /// Returns:
+    ///
+    /// The Response, or `None` if the directory can't be read.
+    fn list(&self, req: &Request, path: &str, directory: &Path) -> Option<Response> {
+        let entries: Vec<ListingEntry> = match self.listing_entries(directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                if log::log_enabled!(log::Level::Error) {
+                    log::error!("Failed to list {}: {}", directory.display(), e);
+                }
+                return None;
+            }
+        };
+        let mut res: Response = if fallback_handlers::prefers_json(req) {
+            Response::json(&listing_json(path, &entries))
+        } else {
+            let has_parent: bool = path.trim_end_matches('/') != self.prefix.trim_end_matches('/');
+            Response::html(listing_html(path, &entries, has_parent))
+        };
+        res.add_vary("Accept");
+        Some(res)
+    }
+
+    /// It reads the entries of a directory for a listing, directories first, then by name.
+    fn listing_entries(&self, directory: &Path) -> Result<Vec<ListingEntry>, Error> {
+        let mut entries: Vec<ListingEntry> = Vec::new();
+        for entry in fs::read_dir(directory)? {
+            let entry: DirEntry = entry?;
+            let name: String = match entry.file_name().into_string() {
+                Ok(name) if !name.starts_with('.') => name,
+                _ => continue
+            };
+            let metadata: Metadata = match self.inside_root(&entry.path()).and_then(|path: PathBuf| fs::metadata(path).ok()) {
+                Some(metadata) => metadata,
+                None => continue
+            };
+            entries.push(ListingEntry {
+                name,
+                directory: metadata.is_dir(),
+                size: if metadata.is_file() { metadata.len() } else { 0 },
+                modified: metadata.modified().ok()
+            });
+        }
+        entries.sort_by(|a: &ListingEntry, b: &ListingEntry| b.directory.cmp(&a.directory).then_with(|| a.name.cmp(&b.name)));
+        Ok(entries)
+    }
+}
+
+/// It serves a file that was already checked to be inside the root.
+fn serve_file(file: &Path) -> Option<Response> {
+    let mut res: Response = Response::ok();
+    match res.set_body_to_path(file) {
+        Ok(_) => Some(res),
+        Err(e) => {
+            if log::log_enabled!(log::Level::Error) {
+                log::error!("Failed to serve {}: {}", file.display(), e);
+            }
+            None
+        }
+    }
+}
+
+/// It renders a listing as an HTML page with a table of the entries, names linking to them.
+///
+/// Arguments:
+///
+/// * `path`: The decoded request path of the directory.
+/// * `entries`: The entries of the directory.
+/// * `has_parent`: Whether to link to the parent directory, which isn't served above the prefix.
+///
+/// Returns:
+///
+/// A String
+fn listing_html(path: &str, entries: &[ListingEntry], has_parent: bool) -> String {
+    let title: String = html_encoder::escape_html(&format!("Index of {}", path));
+    let mut rows: String = String::new();
+    if has_parent {
+        rows.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let (href, name, size) = match entry.directory {
+            true => (format!("{}/", url_encoder::percent_encode(&entry.name)), format!("{}/", entry.name), String::new()),
+            false => (url_encoder::percent_encode(&entry.name), entry.name.clone(), entry.size.to_string())
+        };
+        let modified: String = entry.modified.map(format_http_date).unwrap_or_default();
+        rows.push_str(&format!("<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>\n",
+                               html_encoder::escape_html(&href), html_encoder::escape_html(&name), size, modified));
+    }
+    format!("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n\
+             <table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n{rows}</table>\n</body>\n</html>\n")
+}
+
+/// It renders a listing as JSON, e.g.
+/// `{"path":"/docs/","entries":[{"name":"guide","type":"directory","modified":"..."}]}`.
+/// Files also have a `size` in bytes. The serializer writes strings as they are, so names and the
+/// path are escaped here, like `Problem::to_json` does, since file names can hold quotes.
+///
+/// Arguments:
+///
+/// * `path`: The decoded request path of the directory.
+/// * `entries`: The entries of the directory.
+///
+/// Returns:
+///
+/// A JsonVariant
+fn listing_json(path: &str, entries: &[ListingEntry]) -> JsonVariant {
+    let text = |text: &str| JsonVariant::JsonString(escape_json_string(text));
+    let entries: Vec<JsonVariant> = entries.iter().map(|entry: &ListingEntry| {
+        let mut json: JsonObject = JsonObject::new();
+        json.insert("name", text(&entry.name));
+        json.insert("type", if entry.directory { "directory" } else { "file" }.into());
+        if !entry.directory {
+            json.insert("size", (entry.size as f64).into());
+        }
+        if let Some(modified) = entry.modified {
+            json.insert("modified", format_http_date(modified).into());
+        }
+        JsonVariant::JsonObject(json)
+    }).collect();
+    let mut listing: JsonObject = JsonObject::new();
+    listing.insert("path", text(path));
+    listing.insert("entries", JsonVariant::JsonArray(entries));
+    JsonVariant::JsonObject(listing)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::web::models::request::Request;
+    use crate::web::models::response::Response;
+    use crate::web::models::status_code::StatusCode;
+    use crate::web::request_handling::static_files::StaticFiles;
+
+    fn get(files: &StaticFiles, path: &str, accept: &str) -> Option<Response> {
+        let req: Request = Request::new_without_stream(format!("GET {} HTTP/1.1\nAccept: {}", path, accept).as_bytes(), b"").unwrap();
+        files.serve(&req)
+    }
+
+    #[test]
+    fn refuses_paths_leaving_the_root() {
+        let directory: PathBuf = std::env::temp_dir().join(format!("static-files-{}", std::process::id()));
+        let root: PathBuf = directory.join("public");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(root.join("css/app.css"), "body {}").unwrap();
+        fs::write(root.join("a b.txt"), "spaced").unwrap();
+        fs::write(directory.join("secret.txt"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(directory.join("secret.txt"), root.join("link.txt")).unwrap();
+
+        let files: StaticFiles = StaticFiles::new(&root).unwrap().prefix("/assets/");
+        let app_css: PathBuf = fs::canonicalize(root.join("css/app.css")).unwrap();
+        assert_eq!(files.get_prefix(), "/assets");
+        assert_eq!(files.resolve("/assets/css/app.css"), Some(app_css.clone()));
+        assert_eq!(files.resolve("/assets/./css//app.css"), Some(app_css));
+        assert!(files.resolve("/assets/a%20b.txt").is_some());
+        assert!(files.resolve("/assets2/css/app.css").is_none());
+        assert!(files.resolve("/css/app.css").is_none());
+        assert!(files.resolve("/assets/css").is_none());
+        assert!(files.resolve("/assets/../secret.txt").is_none());
+        assert!(files.resolve("/assets/css/../../secret.txt").is_none());
+        assert!(files.resolve("/assets/%2e%2e/secret.txt").is_none());
+        assert!(files.resolve("/assets/%2E%2E%2Fsecret.txt").is_none());
+        assert!(files.resolve("/assets/..%5csecret.txt").is_none());
+        assert!(files.resolve("/assets/css/app.css%00.txt").is_none());
+        assert!(files.resolve("/assets/%zz").is_none());
+        #[cfg(unix)]
+        assert!(files.resolve("/assets/link.txt").is_none());
+
+        let res: Response = get(&files, "/assets/css/app.css", "*/*").unwrap();
+        assert_eq!(res.headers().get("Content-Type"), Some("text/css"));
+        assert_eq!(res.body_length(), Some(7));
+        assert!(StaticFiles::new(directory.join("missing")).is_err());
+        assert!(StaticFiles::new(directory.join("secret.txt")).is_err());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn serves_directories() {
+        let root: PathBuf = std::env::temp_dir().join(format!("static-directories-{}", std::process::id()));
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::create_dir_all(root.join("files")).unwrap();
+        fs::write(root.join("docs/index.html"), "<h1>Docs</h1>").unwrap();
+        fs::write(root.join("files/a&b.txt"), "12345").unwrap();
+        fs::write(root.join("files/.hidden"), "").unwrap();
+        fs::create_dir_all(root.join("files/sub dir")).unwrap();
+
+        let files: StaticFiles = StaticFiles::new(&root).unwrap();
+        let index: PathBuf = fs::canonicalize(root.join("docs/index.html")).unwrap();
+        assert_eq!(files.resolve("/docs/"), Some(index));
+        assert!(files.resolve("/docs").is_none());
+        assert_eq!(get(&files, "/docs/", "*/*").unwrap().body_length(), Some(13));
+        let redirect: Response = get(&files, "/docs", "*/*").unwrap();
+        assert_eq!(redirect.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(redirect.headers().get("Location"), Some("/docs/"));
+        assert!(get(&files, "/files/", "*/*").is_none());
+        assert!(get(&files, "/files", "*/*").is_none());
+        assert!(get(&files, "/docs/guide/", "*/*").is_none());
+
+        let files: StaticFiles = files.listing(true).index_files(&["default.htm"]);
+        assert_eq!(get(&files, "/files", "*/*").unwrap().status(), StatusCode::MOVED_PERMANENTLY);
+        let html: Response = get(&files, "/files/", "text/html").unwrap();
+        let body: String = String::from_utf8(html.body().to_vec()).unwrap();
+        assert!(body.contains("<title>Index of /files/</title>"));
+        assert!(body.find("href=\"sub%20dir/\"").unwrap() < body.find("href=\"a%26b.txt\">a&amp;b.txt</a></td><td>5</td>").unwrap());
+        assert!(body.contains("href=\"../\""));
+        assert!(!body.contains(".hidden"));
+        assert_eq!(html.headers().get("Vary"), Some("Accept"));
+        let json: Response = get(&files, "/files/", "application/json").unwrap();
+        let body: String = String::from_utf8(json.body().to_vec()).unwrap();
+        assert!(body.contains("\"name\":\"a&b.txt\""));
+        assert!(body.contains("\"size\":5"));
+        assert!(body.contains("\"type\":\"directory\""));
+        assert!(!get(&files, "/", "text/html").unwrap().body().windows(6).any(|w: &[u8]| w == b"\"../\""));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn escapes_names_in_json_listings() {
+        let root: PathBuf = std::env::temp_dir().join(format!("static-json-listing-{}", std::process::id()));
+        fs::create_dir_all(root.join("q\"dir")).unwrap();
+        fs::write(root.join("q\"dir/q\"x\\y.txt"), "").unwrap();
+        let files: StaticFiles = StaticFiles::new(&root).unwrap().listing(true);
+        let res: Response = get(&files, "/q%22dir/", "application/json").unwrap();
+        let body: String = String::from_utf8(res.body().to_vec()).unwrap();
+        assert!(body.contains(r#""path":"/q\"dir/""#), "{}", body);
+        assert!(body.contains(r#""name":"q\"x\\y.txt""#), "{}", body);
+        assert!(!body.contains(r#"q"x"#), "{}", body);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
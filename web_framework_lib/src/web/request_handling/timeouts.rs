@@ -0,0 +1,224 @@
+use std::io::Write;
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use di_ioc_lib::di::providable_trait::Providable;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `Timeouts` bounds how long a connection can hold on to its thread. Install it in the
+/// `IocContainer` to change the defaults, which apply when it isn't installed.
+///
+/// Socket timeouts protect against clients that connect and then send nothing, or send the request
+/// byte by byte (slowloris). The handler deadline can't stop a handler, since threads can't be
+/// killed, but the client gets a `503 Service Unavailable` instead of waiting forever, and a
+/// warning is logged with the route, so the slow handler can be found.
+///
+/// Properties:
+///
+/// * `idle`: How long to wait for the first bytes of the request. 10 seconds by default.
+/// * `read`: How long a single read may block after that. 30 seconds by default.
+/// * `headers`: How long the request line and headers may take to arrive, from their first byte.
+///   30 seconds by default.
+/// * `body`: How long the body may take to arrive, after the headers. 60 seconds by default.
+/// * `write`: How long a single write may block. 30 seconds by default.
+/// * `handler`: How long middleware and the handler may take. No deadline by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeouts {
+    idle: Duration,
+    read: Duration,
+    headers: Duration,
+    body: Duration,
+    write: Duration,
+    handler: Option<Duration>
+}
+
+impl Providable for Timeouts {}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeouts {
+    pub fn new() -> Self {
+        Self {
+            idle: Duration::from_secs(10),
+            read: Duration::from_secs(30),
+            headers: Duration::from_secs(30),
+            body: Duration::from_secs(60),
+            write: Duration::from_secs(30),
+            handler: None
+        }
+    }
+
+    /// A zero duration is ignored, since sockets don't accept it.
+    pub fn idle(mut self, idle: Duration) -> Self {
+        if !idle.is_zero() { self.idle = idle; }
+        self
+    }
+
+    /// A zero duration is ignored, since sockets don't accept it.
+    pub fn read(mut self, read: Duration) -> Self {
+        if !read.is_zero() { self.read = read; }
+        self
+    }
+
+    /// A zero duration is ignored, since sockets don't accept it.
+    pub fn headers(mut self, headers: Duration) -> Self {
+        if !headers.is_zero() { self.headers = headers; }
+        self
+    }
+
+    /// A zero duration is ignored, since sockets don't accept it.
+    pub fn body(mut self, body: Duration) -> Self {
+        if !body.is_zero() { self.body = body; }
+        self
+    }
+
+    /// A zero duration is ignored, since sockets don't accept it.
+    pub fn write(mut self, write: Duration) -> Self {
+        if !write.is_zero() { self.write = write; }
+        self
+    }
+
+    pub fn handler(mut self, handler: Duration) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    pub fn get_idle(&self) -> Duration {
+        self.idle
+    }
+    pub fn get_read(&self) -> Duration {
+        self.read
+    }
+    pub fn get_headers(&self) -> Duration {
+        self.headers
+    }
+    pub fn get_body(&self) -> Duration {
+        self.body
+    }
+    pub fn get_write(&self) -> Duration {
+        self.write
+    }
+    pub fn get_handler(&self) -> Option<Duration> {
+        self.handler
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WatchState {
+    Running,
+    Finished,
+    Fired
+}
+
+/// `Watchdog` answers the client with a prepared response if the handler misses its deadline.
+pub struct Watchdog {
+    state: Arc<(Mutex<WatchState>, Condvar)>
+}
+
+impl Watchdog {
+    /// `start` starts watching a handler.
+    ///
+    /// Arguments:
+    ///
+    /// * `deadline`: How long the handler may take.
+    /// * `stream`: A clone of the connection, to answer on.
+    /// * `timeout_response`: The serialized response sent when the deadline passes.
+    /// * `description`: What is being handled, e.g. "GET /users/{id}", for the warning.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the Watchdog struct.
+    pub fn start(deadline: Duration, mut stream: TcpStream, timeout_response: Vec<u8>, description: String) -> Self {
+        let state: Arc<(Mutex<WatchState>, Condvar)> = Arc::new((Mutex::new(WatchState::Running), Condvar::new()));
+        let watched: Arc<(Mutex<WatchState>, Condvar)> = Arc::clone(&state);
+        thread::spawn(move || {
+            let (lock, finished) = &*watched;
+            let guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+            let (mut guard, _) = finished
+                .wait_timeout_while(guard, deadline, |state: &mut WatchState| *state == WatchState::Running)
+                .unwrap_or_else(|e| e.into_inner());
+            if *guard != WatchState::Running {
+                return;
+            }
+            *guard = WatchState::Fired;
+            drop(guard);
+            if log::log_enabled!(log::Level::Warn) {
+                log::warn!("Handler deadline of {}ms exceeded: {}", deadline.as_millis(), description);
+            }
+            let _ = stream.write_all(&timeout_response);
+            let _ = stream.shutdown(Shutdown::Both);
+        });
+        Self { state }
+    }
+
+    /// It stops watching the handler.
+    ///
+    /// Returns:
+    ///
+    /// `true` if the deadline had already passed, so the client got the timeout response and the
+    /// handler's response must be dropped.
+    pub fn finish(self) -> bool {
+        let (lock, finished) = &*self.state;
+        let mut guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        if *guard == WatchState::Fired {
+            return true;
+        }
+        *guard = WatchState::Finished;
+        finished.notify_one();
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+    use crate::web::request_handling::timeouts::Watchdog;
+
+    fn connection() -> (TcpStream, TcpStream) {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client: TcpStream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn answers_when_deadline_passes() {
+        let (mut client, server) = connection();
+        let watchdog: Watchdog = Watchdog::start(Duration::from_millis(20), server,
+                                                 b"HTTP/1.1 503 Service Unavailable\r\n\r\n".to_vec(), "GET /slow".to_string());
+        thread::sleep(Duration::from_millis(100));
+        assert!(watchdog.finish());
+        let mut received: String = String::new();
+        client.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "HTTP/1.1 503 Service Unavailable\r\n\r\n");
+    }
+
+    #[test]
+    fn stays_quiet_when_handler_finishes() {
+        let (mut client, server) = connection();
+        let watchdog: Watchdog = Watchdog::start(Duration::from_secs(5), server, b"late".to_vec(), "GET /fast".to_string());
+        assert!(!watchdog.finish());
+        // The watchdog drops its clone of the connection without writing anything.
+        let mut received: String = String::new();
+        client.read_to_string(&mut received).unwrap();
+        assert!(received.is_empty());
+    }
+}
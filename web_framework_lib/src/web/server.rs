@@ -8,6 +8,7 @@ use crate::web::models::request::Request;
 use crate::web::models::response::Response;
 
 use crate::web::request_handling::request_handler;
+use crate::web::request_handling::request_handler::HandleError;
 use crate::web::util::parsers::request_parser;
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
@@ -33,6 +34,9 @@ const BANNER: &str = "
 ";
 
 pub type HandlerFunction = fn(req: &Request) -> Response;
+/// Handles whatever went wrong while handling a request. The request is `None` if it couldn't be
+/// parsed.
+pub type ErrorHandlerFunction = fn(req: Option<&Request>, error: &HandleError) -> Response;
 
 pub fn start(port: &str, container: Arc<IocContainer>) {
     let _ = env_logger::try_init();
@@ -0,0 +1,224 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use crate::web::sessions::session::SessionData;
+use crate::web::sessions::session_store::SessionStore;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// How often expired session files are removed.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+/// Numbers the temporary files of saves, so concurrent saves of one session don't share one.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// `FileSessionStore` keeps every session in its own file, so sessions survive restarts. The
+/// first line of a file is the expiry in seconds since the epoch, every further line is a key and a
+/// value, both base64url encoded and separated by a space. Expired files are removed on access,
+/// and once a minute on save.
+///
+/// `touch` extends a session by setting the modification time of its file to the new expiry, so
+/// the data isn't written again. A session expires at the later of the two times.
+///
+/// Properties:
+///
+/// * `dir`: The directory the session files are kept in.
+/// * `last_cleanup`: When expired files were last removed.
+pub struct FileSessionStore {
+    dir: PathBuf,
+    last_cleanup: Mutex<Instant>
+}
+
+impl FileSessionStore {
+    /// `new` creates a store keeping its files in `dir`, creating the directory if needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> io::Result<Self> {
+        let dir: PathBuf = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, last_cleanup: Mutex::new(Instant::now()) })
+    }
+
+    /// It finds the file of a session. Ids with characters that could leave the directory have no
+    /// file.
+    fn path_of(&self, id: &str) -> Option<PathBuf> {
+        let valid: bool = !id.is_empty() && id.chars()
+            .all(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        valid.then(|| self.dir.join(format!("{}.session", id)))
+    }
+
+    /// It removes the files of all expired sessions. Files removed by someone else in the meantime,
+    /// e.g. by `load`, are skipped.
+    pub fn cleanup(&self) -> io::Result<()> {
+        let now: u64 = unix_time(SystemTime::now());
+        for entry in fs::read_dir(&self.dir)? {
+            let path: PathBuf = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "session") {
+                let expired: bool = fs::read_to_string(&path).ok()
+                    .and_then(|content: String| parse(&content))
+                    .is_none_or(|(expires, _)| expiry(&path, expires) <= now);
+                if expired {
+                    match fs::remove_file(&path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d: Duration| d.as_secs()).unwrap_or(0)
+}
+
+/// It returns when a session file expires: the expiry written in it, or the modification time if
+/// `touch` moved that further.
+fn expiry(path: &Path, written: u64) -> u64 {
+    let touched: u64 = fs::metadata(path).and_then(|metadata: fs::Metadata| metadata.modified())
+        .map(unix_time)
+        .unwrap_or(0);
+    written.max(touched)
+}
+
+/// It parses a session file into its expiry and data.
+fn parse(content: &str) -> Option<(u64, SessionData)> {
+    let mut lines = content.lines();
+    let expires: u64 = lines.next()?.trim().parse().ok()?;
+    let mut data: SessionData = SessionData::new();
+    for line in lines.filter(|line: &&str| !line.is_empty()) {
+        let (key, value) = line.split_once(' ')?;
+        let key: String = String::from_utf8(URL_SAFE_NO_PAD.decode(key).ok()?).ok()?;
+        let value: String = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        data.insert(key, value);
+    }
+    Some((expires, data))
+}
+
+impl SessionStore for FileSessionStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        let path: PathBuf = self.path_of(id)?;
+        let (expires, data) = parse(&fs::read_to_string(&path).ok()?)?;
+        if expiry(&path, expires) <= unix_time(SystemTime::now()) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        Some(data)
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> io::Result<()> {
+        let path: PathBuf = self.path_of(id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid session id"))?;
+        {
+            let mut last_cleanup = self.last_cleanup.lock().unwrap_or_else(|e| e.into_inner());
+            if last_cleanup.elapsed() >= CLEANUP_INTERVAL {
+                *last_cleanup = Instant::now();
+                // Failing to remove other sessions' files mustn't fail this save.
+                if let Err(e) = self.cleanup() {
+                    if log::log_enabled!(log::Level::Warn) {
+                        log::warn!("Failed to remove expired sessions: {}", e);
+                    }
+                }
+            }
+        }
+        let mut content: String = unix_time(SystemTime::now() + ttl).to_string();
+        for (key, value) in data {
+            content.push('\n');
+            content.push_str(&URL_SAFE_NO_PAD.encode(key));
+            content.push(' ');
+            content.push_str(&URL_SAFE_NO_PAD.encode(value));
+        }
+        let temp: PathBuf = self.dir.join(format!("{}.{}-{}.tmp", id, process::id(),
+                                                  TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&temp, content)?;
+        fs::rename(temp, path)
+    }
+
+    fn touch(&self, id: &str, ttl: Duration) -> io::Result<()> {
+        let path: PathBuf = self.path_of(id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid session id"))?;
+        match File::options().write(true).open(path) {
+            Ok(file) => file.set_modified(SystemTime::now() + ttl),
+            // A session destroyed in the meantime isn't brought back.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e)
+        }
+    }
+
+    fn destroy(&self, id: &str) -> io::Result<()> {
+        match self.path_of(id).map(fs::remove_file) {
+            Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+    use crate::web::sessions::file_session_store::FileSessionStore;
+    use crate::web::sessions::session::SessionData;
+    use crate::web::sessions::session_store::SessionStore;
+
+    #[test]
+    fn saves_loads_and_expires() {
+        let dir = std::env::temp_dir().join(format!("file_session_store_test_{}", std::process::id()));
+        let store: FileSessionStore = FileSessionStore::new(&dir).unwrap();
+        let mut data: SessionData = SessionData::new();
+        data.insert("user".to_string(), "admin with spaces\nand lines".to_string());
+        store.save("abc_123", &data, Duration::from_secs(60)).unwrap();
+        assert_eq!(store.load("abc_123"), Some(data.clone()));
+        assert_eq!(store.load("../abc_123"), None);
+
+        store.save("expired", &data, Duration::ZERO).unwrap();
+        assert_eq!(store.load("expired"), None);
+        store.save("touched", &data, Duration::ZERO).unwrap();
+        store.touch("touched", Duration::from_secs(60)).unwrap();
+        assert_eq!(store.load("touched"), Some(data.clone()));
+        store.touch("missing", Duration::from_secs(60)).unwrap();
+        assert_eq!(store.load("missing"), None);
+        store.destroy("abc_123").unwrap();
+        assert_eq!(store.load("abc_123"), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saves_one_session_concurrently() {
+        let dir = std::env::temp_dir().join(format!("file_session_store_concurrent_test_{}", std::process::id()));
+        let store: Arc<FileSessionStore> = Arc::new(FileSessionStore::new(&dir).unwrap());
+        let writers: Vec<JoinHandle<()>> = (0..8).map(|writer: usize| {
+            let store: Arc<FileSessionStore> = Arc::clone(&store);
+            thread::spawn(move || {
+                let mut data: SessionData = SessionData::new();
+                data.insert("writer".to_string(), writer.to_string());
+                for _ in 0..20 {
+                    store.save("shared", &data, Duration::from_secs(60)).unwrap();
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(store.load("shared").is_some());
+        // Only the session file is left, no temporary files.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
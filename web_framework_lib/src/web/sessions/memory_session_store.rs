@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::web::sessions::session::SessionData;
+use crate::web::sessions::session_store::SessionStore;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// How often expired sessions are removed.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// `MemorySessionStore` keeps sessions in memory, so they are lost when the server restarts.
+/// Expired sessions are removed on access, and once a minute on save.
+///
+/// Properties:
+///
+/// * `sessions`: The data and expiry of every session, keyed by id.
+/// * `last_cleanup`: When expired sessions were last removed.
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<String, (SessionData, Instant)>>,
+    last_cleanup: Mutex<Instant>
+}
+
+impl Default for MemorySessionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self { sessions: Mutex::new(HashMap::new()), last_cleanup: Mutex::new(Instant::now()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get(id) {
+            Some((_, expires)) if *expires <= Instant::now() => {
+                sessions.remove(id);
+                None
+            },
+            Some((data, _)) => Some(data.clone()),
+            None => None
+        }
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> io::Result<()> {
+        let now: Instant = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let mut last_cleanup = self.last_cleanup.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(*last_cleanup) >= CLEANUP_INTERVAL {
+            sessions.retain(|_, (_, expires)| *expires > now);
+            *last_cleanup = now;
+        }
+        sessions.insert(id.to_string(), (data.clone(), now + ttl));
+        Ok(())
+    }
+
+    fn touch(&self, id: &str, ttl: Duration) -> io::Result<()> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, expires)) = sessions.get_mut(id) {
+            *expires = Instant::now() + ttl;
+        }
+        Ok(())
+    }
+
+    fn destroy(&self, id: &str) -> io::Result<()> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// The values stored in a session.
+pub type SessionData = HashMap<String, String>;
+
+/// The state behind a `Session`, read by the `Sessions` middleware after the handler ran.
+///
+/// Properties:
+///
+/// * `id`: The id of the session, `None` until it is saved for the first time.
+/// * `data`: The values stored in the session.
+/// * `modified`: If the values changed during the request.
+/// * `invalidated`: If the session should be destroyed.
+/// * `regenerate`: If the session should be saved under a new id.
+#[derive(Debug, Default)]
+pub(crate) struct SessionState {
+    pub(crate) id: Option<String>,
+    pub(crate) data: SessionData,
+    pub(crate) modified: bool,
+    pub(crate) invalidated: bool,
+    pub(crate) regenerate: bool
+}
+
+/// `Session` holds values that are kept across the requests of one client, like the logged in
+/// user. The `Sessions` middleware attaches it to every request, so handlers get it with
+/// `req.session()`. Changes are saved to the `SessionStore` after the handler returns.
+///
+/// Properties:
+///
+/// * `state`: The state, shared so handlers can change the session through a `&Request`.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>
+}
+
+impl Session {
+    /// `new` creates an empty session that has not been saved yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `existing` creates a session that was loaded from a store.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the session.
+    /// * `data`: The values loaded from the store.
+    pub fn existing(id: &str, data: SessionData) -> Self {
+        let state: SessionState = SessionState { id: Some(id.to_string()), data, ..SessionState::default() };
+        Self { state: Arc::new(Mutex::new(state)) }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn id(&self) -> Option<String> {
+        self.lock().id.clone()
+    }
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().data.get(key).cloned()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lock().data.is_empty()
+    }
+
+    /// It stores a value, replacing and returning the previous value.
+    pub fn insert(&self, key: &str, value: &str) -> Option<String> {
+        let mut state: MutexGuard<SessionState> = self.lock();
+        state.modified = true;
+        state.data.insert(key.to_string(), value.to_string())
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut state: MutexGuard<SessionState> = self.lock();
+        state.modified = true;
+        state.data.remove(key)
+    }
+
+    /// It destroys the session, e.g. on logout. The client's session cookie is removed.
+    pub fn invalidate(&self) {
+        let mut state: MutexGuard<SessionState> = self.lock();
+        state.data.clear();
+        state.invalidated = true;
+    }
+
+    /// It moves the session to a new id, keeping its values. Call it on login, so an id an
+    /// attacker planted before can't be used to take over the session.
+    pub fn regenerate(&self) {
+        let mut state: MutexGuard<SessionState> = self.lock();
+        state.regenerate = true;
+        state.modified = true;
+    }
+}
@@ -0,0 +1,57 @@
+use std::io;
+use std::time::Duration;
+use crate::web::sessions::session::SessionData;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `SessionStore` keeps the data of sessions between requests. `MemorySessionStore` and
+/// `FileSessionStore` are provided, other backends like a database implement this trait.
+pub trait SessionStore: Send + Sync {
+    /// It loads a session.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the session.
+    ///
+    /// Returns:
+    ///
+    /// The data of the session, or `None` if it doesn't exist or has expired.
+    fn load(&self, id: &str) -> Option<SessionData>;
+
+    /// It saves a session, replacing the previous data.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the session.
+    /// * `data`: The data of the session.
+    /// * `ttl`: How long the session lives without being saved again.
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> io::Result<()>;
+
+    /// It extends a session without writing its data, for requests that only read it, so they
+    /// can't overwrite what a concurrent request saved. Stores that can't do that can keep the
+    /// default, which does nothing, so their sessions expire `ttl` after they were last saved.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the session.
+    /// * `ttl`: How long the session lives from now on.
+    fn touch(&self, _id: &str, _ttl: Duration) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// It deletes a session.
+    ///
+    /// Arguments:
+    ///
+    /// * `id`: The id of the session.
+    fn destroy(&self, id: &str) -> io::Result<()>;
+}
@@ -0,0 +1,328 @@
+use std::fmt::{Display, Formatter};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// What went wrong loading or rendering a template.
+#[derive(Debug)]
+pub enum TemplateError {
+    /// There is no template with the name, or the name tries to leave the template directory.
+    NotFound(String),
+    /// The template exists, but couldn't be read.
+    ReadFailed(String, std::io::Error),
+    /// The template isn't valid.
+    Syntax { template: String, line: usize, message: String },
+    /// Includes or layouts are nested too deeply, most likely because they include themselves.
+    TooDeep(String)
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::NotFound(name) => write!(f, "Template not found: {}", name),
+            TemplateError::ReadFailed(name, e) => write!(f, "Failed to read template {}: {}", name, e),
+            TemplateError::Syntax { template, line, message } => write!(f, "{}:{}: {}", template, line, message),
+            TemplateError::TooDeep(name) => write!(f, "Templates nested too deeply at: {}", name)
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A compiled piece of a template.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Node {
+    /// Text copied as it is.
+    Text(String),
+    /// `{{ path }}`, escaped unless it's followed by `| raw`.
+    Value { path: Vec<String>, raw: bool },
+    /// `{% if path %}`, `{% if not path %}`, with `{% elif %}` turned into a nested `If`.
+    If { path: Vec<String>, negated: bool, then: Vec<Node>, otherwise: Vec<Node> },
+    /// `{% for name in path %}`, with the `{% else %}` part for empty or missing lists.
+    For { name: String, path: Vec<String>, body: Vec<Node>, empty: Vec<Node> },
+    /// `{% include "name" %}`
+    Include(String),
+    /// `{% block name %}`, replaced by the block of the same name in a template extending this one.
+    Block { name: String, body: Vec<Node> }
+}
+
+/// `Template` is a compiled template. Templates are compiled once by `Templates` and cached.
+///
+/// Properties:
+///
+/// * `name`: The name of the template, e.g. "users/show.html".
+/// * `extends`: The layout the template extends, if it starts with `{% extends "name" %}`.
+/// * `nodes`: The compiled template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    name: String,
+    extends: Option<String>,
+    nodes: Vec<Node>
+}
+
+impl Template {
+    /// `compile` parses the source of a template.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The name of the template, used in errors.
+    /// * `source`: The source of the template.
+    ///
+    /// Returns:
+    ///
+    /// The Template, or a TemplateError::Syntax with the line of the mistake.
+    pub fn compile(name: &str, source: &str) -> Result<Self, TemplateError> {
+        let syntax_error = |(line, message): SyntaxError| TemplateError::Syntax {
+            template: name.to_string(), line, message
+        };
+        let mut tokens: Vec<Token> = tokenize(source).map_err(syntax_error)?;
+        let mut extends: Option<String> = None;
+        let first: Option<usize> = tokens.iter()
+            .position(|token: &Token| !matches!(&token.kind, TokenKind::Text(text) if text.trim().is_empty()));
+        if let Some(first) = first {
+            if let TokenKind::Tag(tag) = &tokens[first].kind {
+                if let Some(parent) = tag.strip_prefix("extends ") {
+                    extends = Some(parse_name(parent).map_err(|message: String| syntax_error((tokens[first].line, message)))?);
+                    tokens.drain(..=first);
+                }
+            }
+        }
+        let mut parser: Parser = Parser { tokens, position: 0 };
+        let (nodes, _) = parser.parse_until(&[]).map_err(syntax_error)?;
+        Ok(Self { name: name.to_string(), extends, nodes })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn extends(&self) -> Option<&str> {
+        self.extends.as_deref()
+    }
+    pub(crate) fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+}
+
+#[derive(Debug)]
+enum TokenKind {
+    Text(String),
+    Value(String),
+    Tag(String)
+}
+
+#[derive(Debug)]
+struct Token {
+    kind: TokenKind,
+    line: usize
+}
+
+/// A syntax error, with its line.
+type SyntaxError = (usize, String);
+/// The keyword and arguments of the tag that ended a part, e.g. `elif` and `user.admin`.
+type EndTag = (String, String);
+
+/// It splits a template into text, `{{ values }}` and `{% tags %}`, dropping `{# comments #}`.
+fn tokenize(source: &str) -> Result<Vec<Token>, SyntaxError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut rest: &str = source;
+    let mut line: usize = 1;
+    loop {
+        let next: Option<(usize, &str)> = ["{{", "{%", "{#"].iter()
+            .filter_map(|open: &&str| rest.find(open).map(|index: usize| (index, *open)))
+            .min_by_key(|(index, _)| *index);
+        let (index, open) = match next {
+            Some(next) => next,
+            None => {
+                if !rest.is_empty() {
+                    tokens.push(Token { kind: TokenKind::Text(rest.to_string()), line });
+                }
+                return Ok(tokens);
+            }
+        };
+        if index > 0 {
+            tokens.push(Token { kind: TokenKind::Text(rest[..index].to_string()), line });
+            line += rest[..index].matches('\n').count();
+        }
+        let close: &str = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}"
+        };
+        let after: &str = &rest[index + 2..];
+        let end: usize = after.find(close).ok_or_else(|| (line, format!("Missing {} for {}", close, open)))?;
+        let inner: String = after[..end].trim().to_string();
+        match open {
+            "{{" => tokens.push(Token { kind: TokenKind::Value(inner), line }),
+            "{%" => tokens.push(Token { kind: TokenKind::Tag(inner), line }),
+            _ => {}
+        }
+        line += after[..end].matches('\n').count();
+        rest = &after[end + 2..];
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize
+}
+
+impl Parser {
+    /// It parses nodes until one of the `ends` tags, e.g. `endif`.
+    ///
+    /// Returns:
+    ///
+    /// The nodes, and the keyword and arguments of the end tag. Without end tags, it parses
+    /// everything that's left.
+    fn parse_until(&mut self, ends: &[&str]) -> Result<(Vec<Node>, Option<EndTag>), SyntaxError> {
+        let mut nodes: Vec<Node> = Vec::new();
+        while let Some(token) = self.tokens.get(self.position) {
+            let line: usize = token.line;
+            self.position += 1;
+            let tag: &str = match &token.kind {
+                TokenKind::Text(text) => {
+                    nodes.push(Node::Text(text.clone()));
+                    continue;
+                },
+                TokenKind::Value(value) => {
+                    let (path, raw) = match value.split_once('|') {
+                        Some((path, filter)) if filter.trim() == "raw" => (path, true),
+                        Some((_, filter)) => return Err((line, format!("Unknown filter: {}", filter.trim()))),
+                        None => (value.as_str(), false)
+                    };
+                    nodes.push(Node::Value { path: parse_path(path).map_err(|message: String| (line, message))?, raw });
+                    continue;
+                },
+                TokenKind::Tag(tag) => tag
+            };
+            let (keyword, arguments) = tag.split_once(char::is_whitespace)
+                .map(|(keyword, arguments)| (keyword, arguments.trim()))
+                .unwrap_or((tag, ""));
+            if ends.contains(&keyword) {
+                return Ok((nodes, Some((keyword.to_string(), arguments.to_string()))));
+            }
+            let (keyword, arguments) = (keyword.to_string(), arguments.to_string());
+            let node: Node = match keyword.as_str() {
+                "if" => self.parse_if(&arguments, line)?,
+                "for" => {
+                    let (name, path) = match arguments.split_whitespace().collect::<Vec<&str>>()[..] {
+                        [name, "in", path] => (name.to_string(), parse_path(path).map_err(|message: String| (line, message))?),
+                        _ => return Err((line, "Expected {% for name in list %}".to_string()))
+                    };
+                    let (body, end) = self.expect(&["else", "endfor"], "endfor", line)?;
+                    let empty: Vec<Node> = if end == "else" {
+                        self.expect(&["endfor"], "endfor", line)?.0
+                    } else {
+                        Vec::new()
+                    };
+                    Node::For { name, path, body, empty }
+                },
+                "include" => Node::Include(parse_name(&arguments).map_err(|message: String| (line, message))?),
+                "block" => {
+                    if parse_path(&arguments).map(|path: Vec<String>| path.len()) != Ok(1) {
+                        return Err((line, "Expected {% block name %}".to_string()));
+                    }
+                    let (body, _) = self.expect(&["endblock"], "endblock", line)?;
+                    Node::Block { name: arguments, body }
+                },
+                "extends" => return Err((line, "{% extends %} has to be the first tag".to_string())),
+                _ => return Err((line, format!("Unexpected tag: {{% {} %}}", tag)))
+            };
+            nodes.push(node);
+        }
+        match ends.first() {
+            Some(_) => Err((self.tokens.last().map(|token: &Token| token.line).unwrap_or(1),
+                            format!("Missing {{% {} %}}", ends.last().unwrap_or(&"end")))),
+            None => Ok((nodes, None))
+        }
+    }
+
+    /// It parses until one of the `ends` tags, which has to come.
+    fn expect(&mut self, ends: &[&str], closing: &str, line: usize) -> Result<(Vec<Node>, String), SyntaxError> {
+        match self.parse_until(ends)? {
+            (nodes, Some((keyword, _))) => Ok((nodes, keyword)),
+            (_, None) => Err((line, format!("Missing {{% {} %}}", closing)))
+        }
+    }
+
+    /// It parses an `if` after its tag, with its `elif` and `else` parts.
+    fn parse_if(&mut self, condition: &str, line: usize) -> Result<Node, SyntaxError> {
+        let (negated, path) = match condition.strip_prefix("not ") {
+            Some(path) => (true, path),
+            None => (false, condition)
+        };
+        let path: Vec<String> = parse_path(path).map_err(|message: String| (line, message))?;
+        let (then, end) = match self.parse_until(&["elif", "else", "endif"])? {
+            (then, Some(end)) => (then, end),
+            (_, None) => return Err((line, "Missing {% endif %}".to_string()))
+        };
+        let otherwise: Vec<Node> = match end.0.as_str() {
+            "elif" => vec![self.parse_if(&end.1, line)?],
+            "else" => self.expect(&["endif"], "endif", line)?.0,
+            _ => Vec::new()
+        };
+        Ok(Node::If { path, negated, then, otherwise })
+    }
+}
+
+/// It parses a dotted path like `user.address.city` or `items.0`.
+fn parse_path(path: &str) -> Result<Vec<String>, String> {
+    let path: &str = path.trim();
+    let segments: Vec<String> = path.split('.').map(|segment: &str| segment.to_string()).collect();
+    let valid: bool = segments.iter().all(|segment: &String| !segment.is_empty()
+        && segment.chars().all(|c: char| c.is_alphanumeric() || c == '_' || c == '-'));
+    if !valid {
+        return Err(format!("Invalid name: {:?}", path));
+    }
+    Ok(segments)
+}
+
+/// It parses the quoted template name of an `include` or `extends`.
+fn parse_name(name: &str) -> Result<String, String> {
+    let name: &str = name.trim();
+    name.strip_prefix('"').and_then(|name: &str| name.strip_suffix('"'))
+        .filter(|name: &&str| !name.is_empty() && !name.contains('"'))
+        .map(|name: &str| name.to_string())
+        .ok_or_else(|| format!("Expected a quoted template name, got: {}", name))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::templating::template::{Node, Template, TemplateError};
+
+    fn path(path: &str) -> Vec<String> {
+        path.split('.').map(|segment: &str| segment.to_string()).collect()
+    }
+
+    #[test]
+    fn compiles_templates() {
+        let template: Template = Template::compile("page.html", "{% extends \"base.html\" %}\n\
+            {% block content %}{# note #}{% if not user %}Hi{% elif user.admin %}{{ user.name | raw }}{% endif %}\
+            {% for item in items %}{{ item }}{% else %}-{% endfor %}{% endblock %}").unwrap();
+        assert_eq!(template.extends(), Some("base.html"));
+        assert_eq!(template.nodes(), &[Node::Text("\n".to_string()), Node::Block {
+            name: "content".to_string(),
+            body: vec![
+                Node::If { path: path("user"), negated: true, then: vec![Node::Text("Hi".to_string())], otherwise: vec![
+                    Node::If { path: path("user.admin"), negated: false,
+                        then: vec![Node::Value { path: path("user.name"), raw: true }], otherwise: vec![] }
+                ] },
+                Node::For { name: "item".to_string(), path: path("items"),
+                    body: vec![Node::Value { path: path("item"), raw: false }], empty: vec![Node::Text("-".to_string())] }
+            ]
+        }]);
+
+        let error: TemplateError = Template::compile("broken.html", "<p>\n{% if user %}\n").unwrap_err();
+        assert!(matches!(error, TemplateError::Syntax { line: 2, .. }), "{}", error);
+        assert!(Template::compile("broken.html", "{{ user.name").is_err());
+        assert!(Template::compile("broken.html", "{{ a | upper }}").is_err());
+        assert!(Template::compile("broken.html", "x{% extends \"base.html\" %}").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use crate::web::templating::template::{Node, Template, TemplateError};
+use crate::web::util::encoders::html_encoder::escape_html;
+use crate::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// How deeply includes and layouts may be nested, so a template including itself fails instead of
+/// overflowing the stack.
+const MAX_DEPTH: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref TEMPLATES: RwLock<Arc<Templates>> = RwLock::new(Arc::new(Templates::new()));
+}
+
+/// It replaces the templates `Response::render` uses, e.g. to load them from another directory.
+/// Call it before starting the server.
+pub fn set_templates(templates: Templates) {
+    *TEMPLATES.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(templates);
+}
+
+/// The templates `Response::render` uses, loaded from `src/templates` unless `set_templates` was
+/// called.
+pub fn templates() -> Arc<Templates> {
+    Arc::clone(&TEMPLATES.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// `Templates` loads, compiles and renders HTML templates from a directory. Templates are compiled
+/// the first time they're used and cached, unless caching is turned off, e.g. while editing them.
+///
+/// Values are HTML escaped, unless they're marked `raw`:
+/// ```text
+/// {% extends "layout.html" %}
+/// {% block content %}
+///   <h1>{{ user.name }}</h1>
+///   {% if user.admin %}<a href="/admin">Admin</a>{% elif not user.verified %}Please verify{% endif %}
+///   <ul>{% for order in orders %}<li>{{ order.id }}</li>{% else %}<li>No orders</li>{% endfor %}</ul>
+///   {% include "footer.html" %} {# the footer sees the same values #}
+/// {% endblock %}
+/// ```
+///
+/// Missing values render as nothing. Missing values, empty strings, zero and empty lists are false
+/// in `if`s.
+///
+/// Properties:
+///
+/// * `directory`: Where the templates are, `src/templates` by default, next to `src/public`.
+/// * `caching`: Whether compiled templates are kept.
+/// * `cache`: The compiled templates, by name.
+pub struct Templates {
+    directory: PathBuf,
+    caching: bool,
+    cache: RwLock<HashMap<String, Arc<Template>>>
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Templates {
+    pub fn new() -> Self {
+        Self {
+            directory: PathBuf::from("src/templates"),
+            caching: true,
+            cache: RwLock::new(HashMap::new())
+        }
+    }
+
+    /// It sets the directory the templates are loaded from.
+    pub fn directory<P: AsRef<Path>>(mut self, directory: P) -> Self {
+        self.directory = directory.as_ref().to_path_buf();
+        self
+    }
+
+    /// It turns caching on or off. Without caching, changes to templates show up without a restart.
+    pub fn caching(mut self, caching: bool) -> Self {
+        self.caching = caching;
+        self
+    }
+
+    /// `get` loads and compiles a template, or takes it from the cache.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The path of the template in the directory, e.g. "users/show.html".
+    ///
+    /// Returns:
+    ///
+    /// The compiled Template, or why it couldn't be loaded.
+    pub fn get(&self, name: &str) -> Result<Arc<Template>, TemplateError> {
+        if self.caching {
+            if let Some(template) = self.cache.read().unwrap_or_else(|e| e.into_inner()).get(name) {
+                return Ok(Arc::clone(template));
+            }
+        }
+        let relative: &Path = Path::new(name);
+        if name.is_empty() || !relative.components().all(|component: Component| matches!(component, Component::Normal(_))) {
+            return Err(TemplateError::NotFound(name.to_string()));
+        }
+        let source: String = fs::read_to_string(self.directory.join(relative))
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound => TemplateError::NotFound(name.to_string()),
+                _ => TemplateError::ReadFailed(name.to_string(), e)
+            })?;
+        let template: Arc<Template> = Arc::new(Template::compile(name, &source)?);
+        if self.caching {
+            self.cache.write().unwrap_or_else(|e| e.into_inner()).insert(name.to_string(), Arc::clone(&template));
+        }
+        Ok(template)
+    }
+
+    /// `render` renders a template with its layouts and includes.
+    ///
+    /// Arguments:
+    ///
+    /// * `name`: The path of the template in the directory, e.g. "users/show.html".
+    /// * `context`: The values the template can use. A `HashMap<String, JsonVariant>` can be
+    ///   turned into one with `into()`.
+    ///
+    /// Returns:
+    ///
+    /// The rendered HTML, or why the template couldn't be rendered.
+    pub fn render(&self, name: &str, context: &JsonObject) -> Result<String, TemplateError> {
+        let mut rendered: String = String::new();
+        let mut scope: Scope = Scope { root: context, locals: Vec::new() };
+        self.render_into(name, &mut scope, 0, &mut rendered)?;
+        Ok(rendered)
+    }
+
+    /// It renders a template at a nesting depth, following its `extends` to the outermost layout.
+    fn render_into<'ctx>(&self, name: &str, scope: &mut Scope<'ctx>, depth: usize, rendered: &mut String)
+        -> Result<(), TemplateError> {
+        let mut chain: Vec<Arc<Template>> = vec![self.get(name)?];
+        while let Some(parent) = chain[chain.len() - 1].extends() {
+            if chain.len() > MAX_DEPTH {
+                return Err(TemplateError::TooDeep(parent.to_string()));
+            }
+            let parent: Arc<Template> = self.get(parent)?;
+            chain.push(parent);
+        }
+        let mut blocks: HashMap<&str, &[Node]> = HashMap::new();
+        for template in &chain[..chain.len() - 1] {
+            collect_blocks(template.nodes(), &mut blocks);
+        }
+        let renderer: Renderer = Renderer { templates: self, blocks, depth };
+        renderer.render(chain[chain.len() - 1].nodes(), scope, rendered)
+    }
+}
+
+/// It collects the blocks of a template extending a layout. Blocks already collected from a
+/// template further down the chain win.
+fn collect_blocks<'a>(nodes: &'a [Node], blocks: &mut HashMap<&'a str, &'a [Node]>) {
+    for node in nodes {
+        if let Node::Block { name, body } = node {
+            blocks.entry(name.as_str()).or_insert(body.as_slice());
+            collect_blocks(body, blocks);
+        }
+    }
+}
+
+/// The values a template sees: the context, and the variables of the loops it is in.
+struct Scope<'ctx> {
+    root: &'ctx JsonObject,
+    locals: Vec<(String, &'ctx JsonVariant)>
+}
+
+impl<'ctx> Scope<'ctx> {
+    /// It looks up a dotted path, starting with the innermost loop variable of that name.
+    fn lookup(&self, path: &[String]) -> Option<&'ctx JsonVariant> {
+        let (first, rest) = path.split_first()?;
+        let mut value: &'ctx JsonVariant = match self.locals.iter().rev().find(|(name, _)| name == first) {
+            Some((_, value)) => value,
+            None => self.root.get(first)?
+        };
+        for segment in rest {
+            value = match value {
+                JsonVariant::JsonObject(object) => object.get(segment)?,
+                JsonVariant::JsonArray(items) => items.get(segment.parse::<usize>().ok()?)?,
+                JsonVariant::JsonString(_) | JsonVariant::JsonNumber(_) => return None
+            };
+        }
+        Some(value)
+    }
+}
+
+struct Renderer<'a> {
+    templates: &'a Templates,
+    blocks: HashMap<&'a str, &'a [Node]>,
+    depth: usize
+}
+
+impl Renderer<'_> {
+    fn render<'ctx>(&self, nodes: &[Node], scope: &mut Scope<'ctx>, rendered: &mut String) -> Result<(), TemplateError> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => rendered.push_str(text),
+                Node::Value { path, raw } => {
+                    let value: String = match scope.lookup(path) {
+                        Some(JsonVariant::JsonString(value)) => value.clone(),
+                        Some(value) => value.to_string(),
+                        None => String::new()
+                    };
+                    rendered.push_str(&if *raw { value } else { escape_html(&value) });
+                },
+                Node::If { path, negated, then, otherwise } => {
+                    let truthy: bool = match scope.lookup(path) {
+                        Some(JsonVariant::JsonString(value)) => !value.is_empty(),
+                        Some(JsonVariant::JsonArray(items)) => !items.is_empty(),
+                        Some(JsonVariant::JsonNumber(n)) => *n != 0.0,
+                        Some(JsonVariant::JsonObject(_)) => true,
+                        None => false
+                    };
+                    self.render(if truthy != *negated { then } else { otherwise }, scope, rendered)?;
+                },
+                Node::For { name, path, body, empty } => match scope.lookup(path) {
+                    Some(JsonVariant::JsonArray(items)) if !items.is_empty() => {
+                        for item in items {
+                            scope.locals.push((name.clone(), item));
+                            let result: Result<(), TemplateError> = self.render(body, scope, rendered);
+                            scope.locals.pop();
+                            result?;
+                        }
+                    },
+                    _ => self.render(empty, scope, rendered)?
+                },
+                Node::Include(name) => {
+                    if self.depth >= MAX_DEPTH {
+                        return Err(TemplateError::TooDeep(name.clone()));
+                    }
+                    self.templates.render_into(name, scope, self.depth + 1, rendered)?;
+                },
+                Node::Block { name, body } => {
+                    let body: &[Node] = self.blocks.get(name.as_str()).copied().unwrap_or(body);
+                    self.render(body, scope, rendered)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::web::templating::template::TemplateError;
+    use crate::web::templating::template_engine::Templates;
+    use crate::web::util::parsers::json_parser::{JsonObject, JsonVariant};
+
+    #[test]
+    fn renders_layouts_loops_and_includes() {
+        let directory: PathBuf = std::env::temp_dir().join(format!("templates-{}", std::process::id()));
+        fs::create_dir_all(directory.join("partials")).unwrap();
+        fs::write(directory.join("layout.html"), "<title>{% block title %}Site{% endblock %}</title>\
+            <main>{% block content %}{% endblock %}</main>").unwrap();
+        fs::write(directory.join("partials/item.html"), "<li>{{ item.name }}</li>").unwrap();
+        fs::write(directory.join("page.html"), "{% extends \"layout.html\" %}\
+            {% block content %}<h1>{{ title }}</h1>{{ intro | raw }}\
+            <ul>{% for item in items %}{% include \"partials/item.html\" %}{% else %}<li>none</li>{% endfor %}</ul>\
+            {% if not admin %}guest{% endif %}{% endblock %}").unwrap();
+        fs::write(directory.join("loop.html"), "{% include \"loop.html\" %}").unwrap();
+        let templates: Templates = Templates::new().directory(&directory);
+
+        let items: Vec<JsonVariant> = ["<script>", "b&b"].iter().map(|name: &&str| {
+            let mut item: JsonObject = JsonObject::new();
+            item.insert("name", JsonVariant::from(*name));
+            JsonVariant::JsonObject(item)
+        }).collect();
+        let mut context: HashMap<String, JsonVariant> = HashMap::new();
+        context.insert("title".to_string(), JsonVariant::from("Tom & \"Jerry\""));
+        context.insert("intro".to_string(), JsonVariant::from("<p>Hi</p>"));
+        context.insert("items".to_string(), JsonVariant::JsonArray(items));
+        let rendered: String = templates.render("page.html", &context.into()).unwrap();
+        assert_eq!(rendered, "<title>Site</title><main><h1>Tom &amp; &quot;Jerry&quot;</h1><p>Hi</p>\
+            <ul><li>&lt;script&gt;</li><li>b&amp;b</li></ul>guest</main>");
+
+        assert!(matches!(templates.render("loop.html", &JsonObject::new()), Err(TemplateError::TooDeep(_))));
+        assert!(matches!(templates.get("../page.html"), Err(TemplateError::NotFound(_))));
+        assert!(matches!(templates.get("missing.html"), Err(TemplateError::NotFound(_))));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// It signs a value with HMAC-SHA256, so it can be handed to a client and checked when it comes
+/// back, e.g. in a cookie.
+///
+/// Arguments:
+///
+/// * `secret`: The server secret.
+/// * `value`: The value to sign.
+///
+/// Returns:
+///
+/// The value followed by a dot and the base64url encoded signature.
+pub fn sign(secret: &[u8], value: &str) -> String {
+    let mut mac: HmacSha256 = HmacSha256::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(value.as_bytes());
+    format!("{}.{}", value, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+}
+
+/// It checks a value signed with `sign`, in constant time.
+///
+/// Arguments:
+///
+/// * `secret`: The server secret.
+/// * `signed`: The signed value.
+///
+/// Returns:
+///
+/// The value without the signature, or `None` if the signature is missing or wrong.
+pub fn verify<'a>(secret: &[u8], signed: &'a str) -> Option<&'a str> {
+    let (value, signature) = signed.rsplit_once('.')?;
+    let signature: Vec<u8> = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac: HmacSha256 = HmacSha256::new_from_slice(secret).ok()?;
+    mac.update(value.as_bytes());
+    mac.verify_slice(&signature).ok().map(|_| value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::encoders::hmac_signer::{sign, verify};
+
+    #[test]
+    fn signs_and_verifies() {
+        let signed: String = sign(b"secret", "session-id");
+        assert_eq!(verify(b"secret", &signed), Some("session-id"));
+        assert_eq!(verify(b"other secret", &signed), None);
+        assert_eq!(verify(b"secret", &signed.replace("session", "forged")), None);
+        assert_eq!(verify(b"secret", "session-id"), None);
+    }
+}
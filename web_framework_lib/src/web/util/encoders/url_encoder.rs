@@ -67,4 +67,53 @@ pub fn encode(decoded: &str) -> String {
         encoded = encoded.replace(k, v);
     }
     encoded
-}
\ No newline at end of file
+}
+
+/// It percent-encodes every byte of a string except the unreserved characters (letters, digits,
+/// `-`, `.`, `_` and `~`), unlike `encode`, so the result is safe as a single path segment.
+///
+/// Arguments:
+///
+/// * `decoded`: The string to be encoded, e.g. "a/b é".
+///
+/// Returns:
+///
+/// The encoded String, e.g. "a%2Fb%20%C3%A9".
+pub fn percent_encode(decoded: &str) -> String {
+    let mut encoded: String = String::with_capacity(decoded.len());
+    for byte in decoded.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    encoded
+}
+
+/// It decodes every percent-encoded byte of a string, unlike `decode`, which only decodes the
+/// reserved and unsafe characters.
+///
+/// Arguments:
+///
+/// * `encoded`: The string to be decoded, e.g. "a%2Fb%C3%A9".
+///
+/// Returns:
+///
+/// The decoded String, or `None` if an escape is malformed or the result isn't UTF-8.
+pub fn percent_decode(encoded: &str) -> Option<String> {
+    let bytes: &[u8] = encoded.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex: &str = encoded.get(i + 1..i + 3)
+                .filter(|hex: &&str| hex.bytes().all(|b: u8| b.is_ascii_hexdigit()))?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -12,10 +12,19 @@ use proc_macros_derive::StrFromEnum;
 //     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
 //                               ░                    ░
 
-#[derive(EnumFromStr, StrFromEnum, Eq, PartialEq, Hash, Debug)]
+#[derive(EnumFromStr, StrFromEnum, Eq, PartialEq, Hash, Debug, Clone, Copy)]
 pub enum HttpMethod {
     GET,
     POST,
     PUT,
-    DELETE
+    DELETE,
+    PATCH,
+    HEAD,
+    OPTIONS
+}
+
+impl HttpMethod {
+    /// Every method, in the order they are declared.
+    pub const ALL: [HttpMethod; 7] = [HttpMethod::GET, HttpMethod::POST, HttpMethod::PUT,
+        HttpMethod::DELETE, HttpMethod::PATCH, HttpMethod::HEAD, HttpMethod::OPTIONS];
 }
\ No newline at end of file
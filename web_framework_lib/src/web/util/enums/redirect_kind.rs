@@ -0,0 +1,40 @@
+use crate::web::models::status_code::StatusCode;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `RedirectKind` is the kind of redirect `Response::redirect` answers with. Permanent redirects
+/// are cached by browsers, and only 307 and 308 guarantee the method and body are kept.
+#[derive(Eq, PartialEq, Hash, Debug, Clone, Copy)]
+pub enum RedirectKind {
+    /// `301 Moved Permanently`: the resource moved for good. Browsers may turn a POST into a GET.
+    MovedPermanently,
+    /// `302 Found`: the resource is somewhere else for now. Browsers may turn a POST into a GET.
+    Found,
+    /// `303 See Other`: the result is somewhere else, fetch it with GET, e.g. after a form post.
+    SeeOther,
+    /// `307 Temporary Redirect`: like 302, but the method and body are kept.
+    TemporaryRedirect,
+    /// `308 Permanent Redirect`: like 301, but the method and body are kept.
+    PermanentRedirect
+}
+
+impl RedirectKind {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RedirectKind::MovedPermanently => StatusCode::MOVED_PERMANENTLY,
+            RedirectKind::Found => StatusCode::FOUND,
+            RedirectKind::SeeOther => StatusCode::SEE_OTHER,
+            RedirectKind::TemporaryRedirect => StatusCode::TEMPORARY_REDIRECT,
+            RedirectKind::PermanentRedirect => StatusCode::PERMANENT_REDIRECT
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// It parses the `Cookie` request header into name-value pairs, as described in RFC 6265. Pairs
+/// without a `=` or without a name are skipped, and double quotes around a value are removed.
+///
+/// Arguments:
+///
+/// * `header`: The value of the `Cookie` header, e.g. "theme=dark; session_id=abc".
+///
+/// Returns:
+///
+/// The cookies in the order they were sent.
+pub fn parse_cookie_header(header: &str) -> Vec<(&str, &str)> {
+    header.split(';')
+        .filter_map(|pair: &str| pair.split_once('='))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| {
+            let unquoted: &str = value.strip_prefix('"')
+                .and_then(|value: &str| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (name, unquoted)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::parsers::cookie_parser::parse_cookie_header;
+
+    #[test]
+    fn parses_pairs() {
+        assert_eq!(parse_cookie_header("theme=dark; session_id=\"abc=\";flag; =x; empty="),
+                   vec![("theme", "dark"), ("session_id", "abc="), ("empty", "")]);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::web::util::encoders::url_encoder;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// It parses an `application/x-www-form-urlencoded` body, as sent by HTML forms. `+` is decoded
+/// as a space, pairs that can't be decoded are skipped.
+///
+/// Arguments:
+///
+/// * `body`: The body of the request, e.g. "name=J%C3%BCrgen&city=New+York".
+///
+/// Returns:
+///
+/// The names and values of the fields, in the order they were sent.
+pub fn parse_form_urlencoded(body: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(body)
+        .split('&')
+        .filter(|pair: &&str| !pair.is_empty())
+        .filter_map(|pair: &str| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let name: String = url_encoder::percent_decode(&name.replace('+', " "))?;
+            let value: String = url_encoder::percent_decode(&value.replace('+', " "))?;
+            Some((name, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::parsers::form_parser::parse_form_urlencoded;
+
+    #[test]
+    fn parses_fields() {
+        let fields: Vec<(String, String)> = parse_form_urlencoded(b"name=J%C3%BCrgen&city=New+York&bad=%G1&flag");
+        assert_eq!(fields, vec![
+            ("name".to_string(), "Jürgen".to_string()),
+            ("city".to_string(), "New York".to_string()),
+            ("flag".to_string(), String::new())
+        ]);
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// It converts days since the epoch to a year, month and day of the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z: i64 = days + 719_468;
+    let era: i64 = z.div_euclid(146_097);
+    let day_of_era: i64 = z.rem_euclid(146_097);
+    let year_of_era: i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp: i64 = (5 * day_of_year + 2) / 153;
+    let day: u32 = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month: u32 = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year: i64 = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// It converts a year, month and day of the proleptic Gregorian calendar to days since the epoch.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year: i64 = year - i64::from(month <= 2);
+    let era: i64 = year.div_euclid(400);
+    let year_of_era: i64 = year.rem_euclid(400);
+    let month: i64 = i64::from(month);
+    let day_of_year: i64 = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// It parses an HTTP date in the preferred IMF-fixdate format, e.g. "Sun, 06 Nov 1994 08:49:37 GMT",
+/// as sent in `If-Modified-Since` and `If-Unmodified-Since`. The obsolete RFC 850 and asctime
+/// formats are not supported, and are treated like invalid dates.
+///
+/// Arguments:
+///
+/// * `value`: The date to parse.
+///
+/// Returns:
+///
+/// The time, or `None` if the date is invalid.
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let (_weekday, rest) = value.trim().split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    if parts.len() != 5 || parts[4] != "GMT" {
+        return None;
+    }
+    let day: u32 = parts[0].parse().ok().filter(|day: &u32| (1..=31).contains(day))?;
+    let month: u32 = MONTHS.iter().position(|month: &&str| *month == parts[1])? as u32 + 1;
+    let year: i64 = parts[2].parse().ok().filter(|year: &i64| *year >= 1970)?;
+    let time: Vec<u64> = parts[3].split(':')
+        .map(|part: &str| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    if time.len() != 3 || time[0] > 23 || time[1] > 59 || time[2] > 60 {
+        return None;
+    }
+    let days: u64 = days_from_civil(year, month, day) as u64;
+    let secs: u64 = days * 86_400 + time[0] * 3600 + time[1] * 60 + time[2];
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// It formats a time as an HTTP date (IMF-fixdate), as used by the `Date`, `Expires` and
+/// `Last-Modified` headers.
+///
+/// Arguments:
+///
+/// * `time`: The time to format. Times before the epoch are formatted as the epoch.
+///
+/// Returns:
+///
+/// A String like "Sun, 06 Nov 1994 08:49:37 GMT".
+pub fn format_http_date(time: SystemTime) -> String {
+    let secs: i64 = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    let days: i64 = secs.div_euclid(86_400);
+    let secs_of_day: i64 = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!("{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+            WEEKDAYS[days.rem_euclid(7) as usize], day, MONTHS[month as usize - 1], year,
+            secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60)
+}
+
+/// It formats a time as used by the Common and Combined Log Formats.
+///
+/// Arguments:
+///
+/// * `time`: The time to format. Times before the epoch are formatted as the epoch.
+///
+/// Returns:
+///
+/// A String like "06/Nov/1994:08:49:37 +0000".
+pub fn format_common_log_date(time: SystemTime) -> String {
+    let secs: i64 = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    let secs_of_day: i64 = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    format!("{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+            day, MONTHS[month as usize - 1], year,
+            secs_of_day / 3600, secs_of_day % 3600 / 60, secs_of_day % 60)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, UNIX_EPOCH};
+    use crate::web::util::parsers::http_date_parser::{format_common_log_date, format_http_date, parse_http_date};
+
+    #[test]
+    fn formats_imf_fixdate() {
+        assert_eq!(format_http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(784_111_777)), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(951_782_400)), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), Some(UNIX_EPOCH + Duration::from_secs(784_111_777)));
+        assert_eq!(parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT"), Some(UNIX_EPOCH + Duration::from_secs(951_782_400)));
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(format_common_log_date(UNIX_EPOCH + Duration::from_secs(784_111_777)), "06/Nov/1994:08:49:37 +0000");
+    }
+}
@@ -17,35 +17,85 @@ pub enum JsonVariant {
     JsonObject(JsonObject),
     JsonArray(Vec<JsonVariant>),
     JsonString(String),
+    /// A number. The parser keeps unquoted values as strings, so numbers only come from code, e.g.
+    /// the `status` of a `Problem`.
+    JsonNumber(f64),
 }
 
 impl ToString for JsonVariant {
     fn to_string(&self) -> String {
         match self {
             JsonVariant::JsonObject(obj) => {
-                let mut string: String = String::from("{");
-                for (key, variant) in &obj.map {
-                    string = string
-                        .add(&format!(r#""{}""#, key))
-                        .add(":")
-                        .add(&variant.to_string())
-                        .add(",");
-                }
-                string.add("}")
+                let members: Vec<String> = obj.map.iter()
+                    .map(|(key, variant): (&String, &JsonVariant)| {
+                        format!(r#""{}":{}"#, key, variant.to_string())
+                    }).collect();
+                String::from("{").add(&members.join(",")).add("}")
             },
             JsonVariant::JsonArray(vec) => {
-                let mut string: String = String::from("[");
-                for current in vec {
-                    string = string.add(&current.to_string()).add(",");
-                }
-                string = string.add("]");
-                string
+                let elements: Vec<String> = vec.iter()
+                    .map(|current: &JsonVariant| current.to_string())
+                    .collect();
+                String::from("[").add(&elements.join(",")).add("]")
             }
             JsonVariant::JsonString(s) => {
                 String::from(format!(r#""{}""#, s))
             }
+            JsonVariant::JsonNumber(n) if n.is_finite() => n.to_string(),
+            JsonVariant::JsonNumber(_) => String::from("null")
+        }
+    }
+}
+
+/// It escapes a string for use inside a JSON string literal, so quotes, backslashes and control
+/// characters can't break out of it. Strings parsed by `parse_into_json_object` keep their escapes,
+/// so this is for strings from other sources, e.g. request headers.
+///
+/// Arguments:
+///
+/// * `s`: The string to escape.
+///
+/// Returns:
+///
+/// The escaped string, without the surrounding quotes.
+pub fn escape_json_string(s: &str) -> String {
+    let mut escaped: String = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c)
         }
     }
+    escaped
+}
+
+/// It escapes every key and string of an object with `escape_json_string`, in nested objects and
+/// arrays too, so an object built from untrusted text can be serialized safely.
+///
+/// Arguments:
+///
+/// * `obj`: The object to escape.
+///
+/// Returns:
+///
+/// A copy of the object with every key and string escaped.
+pub fn escape_json_object(obj: &JsonObject) -> JsonObject {
+    fn escape_variant(variant: &JsonVariant) -> JsonVariant {
+        match variant {
+            JsonVariant::JsonObject(obj) => JsonVariant::JsonObject(escape_json_object(obj)),
+            JsonVariant::JsonArray(vec) => JsonVariant::JsonArray(vec.iter().map(escape_variant).collect()),
+            JsonVariant::JsonString(s) => JsonVariant::JsonString(escape_json_string(s)),
+            JsonVariant::JsonNumber(n) => JsonVariant::JsonNumber(*n)
+        }
+    }
+    JsonObject::from(obj.map.iter()
+        .map(|(key, variant): (&String, &JsonVariant)| (escape_json_string(key), escape_variant(variant)))
+        .collect::<HashMap<String, JsonVariant>>())
 }
 
 /// `JsonObject` is a `HashMap` of `String`s to `JsonVariant`s.
@@ -76,7 +126,57 @@ pub enum JsonGetError {
     NotFound
 }
 
+impl From<String> for JsonVariant {
+    fn from(s: String) -> Self {
+        JsonVariant::JsonString(s)
+    }
+}
+
+impl From<f64> for JsonVariant {
+    fn from(n: f64) -> Self {
+        JsonVariant::JsonNumber(n)
+    }
+}
+
+impl From<&str> for JsonVariant {
+    fn from(s: &str) -> Self {
+        JsonVariant::JsonString(s.to_string())
+    }
+}
+
+impl From<HashMap<String, JsonVariant>> for JsonObject {
+    fn from(map: HashMap<String, JsonVariant>) -> Self {
+        Self { map }
+    }
+}
+
+impl Default for JsonObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JsonObject {
+    /// `new` creates an empty `JsonObject`, to be filled with `insert`.
+    ///
+    /// Returns:
+    ///
+    /// A new instance of the JsonObject struct.
+    pub fn new() -> Self {
+        Self { map: HashMap::new() }
+    }
+
+    /// It inserts a value into the object, replacing whatever was stored under the key before.
+    ///
+    /// Arguments:
+    ///
+    /// * `k`: The key of the member.
+    /// * `v`: The value of the member.
+    pub fn insert(&mut self, k: &str, v: JsonVariant) -> &mut Self {
+        self.map.insert(k.to_string(), v);
+        self
+    }
+
     pub fn get(&self, k: &str) -> Option<&JsonVariant> {
         self.map.get(k)
     }
@@ -344,8 +444,9 @@ impl JsonObject {
 /// A Result<JsonObject, JsonParseError>
 pub fn parse_into_json_object(bytes: &[u8]) -> Result<JsonObject, JsonParseError> {
     let json: Result<JsonObject, JsonParseError> = JsonObject::parse_object(bytes);
-    if log::log_enabled!(log::Level::Debug) {
-        log::debug!("Parsed Json: {:?}", json)
+    // Only the outcome is logged, bodies can hold passwords and tokens.
+    if json.is_err() && log::log_enabled!(log::Level::Debug) {
+        log::debug!("Failed to parse Json body of {} bytes", bytes.len())
     }
     json
 }
@@ -0,0 +1,61 @@
+use crate::web::util::encoders::url_encoder;
+
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// It normalises a request path, so that paths that point to the same resource compare equal,
+/// e.g. "//admin/./users/" and "/%61dmin/users/" both become "/admin/users/". The path is
+/// percent-decoded first, so encoded slashes and dots count as such, then empty and `.` segments
+/// are dropped. A trailing slash is kept, since it tells a directory from a file.
+///
+/// Arguments:
+///
+/// * `path`: The request path, still percent-encoded and without the query.
+///
+/// Returns:
+///
+/// The normalised path, or `None` if it has a `..` segment, a malformed escape, or doesn't decode
+/// to UTF-8.
+pub fn normalize_path(path: &str) -> Option<String> {
+    let decoded: String = url_encoder::percent_decode(path)?;
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in decoded.split('/').filter(|segment: &&str| !segment.is_empty() && *segment != ".") {
+        if segment == ".." {
+            return None;
+        }
+        segments.push(segment);
+    }
+    let trailing_slash: bool = !segments.is_empty() && (decoded.ends_with('/') || decoded.ends_with("/."));
+    Some(format!("/{}{}", segments.join("/"), if trailing_slash { "/" } else { "" }))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::parsers::path_parser::normalize_path;
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(normalize_path("/admin/secret.txt").as_deref(), Some("/admin/secret.txt"));
+        assert_eq!(normalize_path("//admin/secret.txt").as_deref(), Some("/admin/secret.txt"));
+        assert_eq!(normalize_path("/./admin/secret.txt").as_deref(), Some("/admin/secret.txt"));
+        assert_eq!(normalize_path("/%61dmin/secret.txt").as_deref(), Some("/admin/secret.txt"));
+        assert_eq!(normalize_path("/docs/").as_deref(), Some("/docs/"));
+        assert_eq!(normalize_path("/docs/.").as_deref(), Some("/docs/"));
+        assert_eq!(normalize_path("/a%20b%2Fc").as_deref(), Some("/a b/c"));
+        assert_eq!(normalize_path("").as_deref(), Some("/"));
+        assert_eq!(normalize_path("//").as_deref(), Some("/"));
+        assert_eq!(normalize_path("/admin/../secret.txt"), None);
+        assert_eq!(normalize_path("/%2e%2e/secret.txt"), None);
+        assert_eq!(normalize_path("/a%2F..%2Fb"), None);
+        assert_eq!(normalize_path("/%zz"), None);
+        assert_eq!(normalize_path("/%FF"), None);
+    }
+}
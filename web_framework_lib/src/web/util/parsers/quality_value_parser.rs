@@ -0,0 +1,157 @@
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// `QualityValue` is a single, weighted entry of a header like `Accept` or `Accept-Encoding`.
+///
+/// Properties:
+///
+/// * `value`: The media range or coding, e.g. `text/html`, `application/*` or `gzip`.
+/// * `quality`: The `q` weight of the entry, between 0.0 and 1.0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityValue {
+    value: String,
+    quality: f32
+}
+
+impl QualityValue {
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    pub fn quality(&self) -> f32 {
+        self.quality
+    }
+
+    /// It returns how specific this entry is when matched against `candidate`, or `None` if it
+    /// doesn't match at all. An exact match beats `type/*`, which beats `*/*` and `*`.
+    ///
+    /// Arguments:
+    ///
+    /// * `candidate`: The media type or coding we want to know the quality of.
+    ///
+    /// Returns:
+    ///
+    /// An Option<u8>, where a higher number is a more specific match.
+    fn specificity(&self, candidate: &str) -> Option<u8> {
+        if self.value.eq_ignore_ascii_case(candidate) {
+            return Some(2);
+        }
+        if self.value == "*" || self.value == "*/*" {
+            return Some(0);
+        }
+        match (self.value.split_once('/'), candidate.split_once('/')) {
+            (Some((range_type, "*")), Some((candidate_type, _)))
+            if range_type.eq_ignore_ascii_case(candidate_type) => Some(1),
+            _ => None
+        }
+    }
+}
+
+/// It splits a header value by commas, and parses every entry into a `QualityValue`. Entries
+/// without a `q` parameter get a quality of 1.0. The result is sorted by quality, highest first,
+/// and keeps the header order for entries of equal quality.
+///
+/// Arguments:
+///
+/// * `header_value`: The raw header value, e.g. `text/html, application/json;q=0.9`.
+///
+/// Returns:
+///
+/// A Vec<QualityValue>
+pub fn parse_quality_values(header_value: &str) -> Vec<QualityValue> {
+    let mut quality_values: Vec<QualityValue> = header_value.split(',')
+        .filter_map(|entry: &str| {
+            let mut params = entry.split(';');
+            let value: &str = params.next()?.trim();
+            if value.is_empty() {
+                return None;
+            }
+            let quality: f32 = params
+                .filter_map(|param: &str| param.trim().split_once('='))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, v)| v.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+            Some(QualityValue { value: value.to_string(), quality })
+        }).collect();
+    quality_values.sort_by(|a: &QualityValue, b: &QualityValue| b.quality.total_cmp(&a.quality));
+    quality_values
+}
+
+/// It returns the quality the client assigned to `candidate`, using the most specific entry that
+/// matches it. Anything that isn't matched has a quality of 0.0.
+///
+/// Arguments:
+///
+/// * `quality_values`: The parsed header.
+/// * `candidate`: The media type or coding to look up.
+///
+/// Returns:
+///
+/// A f32 between 0.0 and 1.0
+pub fn quality_of(quality_values: &[QualityValue], candidate: &str) -> f32 {
+    quality_values.iter()
+        .filter_map(|qv: &QualityValue| qv.specificity(candidate).map(|s: u8| (s, qv.quality)))
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, quality)| quality)
+        .unwrap_or(0.0)
+}
+
+/// It picks the entry of `offered` the client prefers the most. Ties are won by whatever comes
+/// first in `offered`, so the server's own preference decides when the client doesn't care.
+///
+/// Arguments:
+///
+/// * `header_value`: The raw header value, e.g. `gzip;q=1.0, deflate;q=0.5`.
+/// * `offered`: What the server is able to produce, in order of preference.
+///
+/// Returns:
+///
+/// The preferred entry of `offered`, or `None` if the client accepts none of them.
+pub fn negotiate<'a>(header_value: &str, offered: &[&'a str]) -> Option<&'a str> {
+    let quality_values: Vec<QualityValue> = parse_quality_values(header_value);
+    let mut best: Option<(&'a str, f32)> = None;
+    for candidate in offered {
+        let quality: f32 = quality_of(&quality_values, candidate);
+        if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+            best = Some((candidate, quality));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::parsers::quality_value_parser::{negotiate, parse_quality_values, quality_of};
+
+    #[test]
+    fn parses_and_sorts_by_quality() {
+        let parsed = parse_quality_values("text/html;q=0.5, application/json, */*;q=0.1");
+        assert_eq!(parsed[0].value(), "application/json");
+        assert_eq!(parsed[1].value(), "text/html");
+        assert_eq!(parsed[2].quality(), 0.1);
+    }
+
+    #[test]
+    fn most_specific_match_wins() {
+        let parsed = parse_quality_values("text/*;q=0.3, text/html;q=0.7, */*;q=0.1");
+        assert_eq!(quality_of(&parsed, "text/html"), 0.7);
+        assert_eq!(quality_of(&parsed, "text/plain"), 0.3);
+        assert_eq!(quality_of(&parsed, "image/png"), 0.1);
+    }
+
+    #[test]
+    fn negotiates_offered_values() {
+        assert_eq!(negotiate("application/json", &["text/html", "application/json"]),
+                   Some("application/json"));
+        assert_eq!(negotiate("*/*", &["text/html", "application/json"]), Some("text/html"));
+        assert_eq!(negotiate("gzip;q=0, deflate", &["gzip"]), None);
+    }
+}
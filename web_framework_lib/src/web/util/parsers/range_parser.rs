@@ -0,0 +1,143 @@
+//  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
+// ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
+// ▒█░ █ ░█ ▒███   ▒██░▄▄▄░▒██▒ ▄██▒██░    ▒██  ▀█▄  ░██   █▌
+// ░█░ █ ░█ ▒▓█  ▄ ░▓█  ██▓▒██░█▀  ▒██░    ░██▄▄▄▄██ ░▓█▄   ▌
+// ░░██▒██▓ ░▒████▒░▒▓███▀▒░▓█  ▀█▓░██████▒ ▓█   ▓██▒░▒████▓
+// ░ ▓░▒ ▒  ░░ ▒░ ░ ░▒   ▒ ░▒▓███▀▒░ ▒░▓  ░ ▒▒   ▓▒█░ ▒▒▓  ▒
+//   ▒ ░ ░   ░ ░  ░  ░   ░ ▒░▒   ░ ░ ░ ▒  ░  ▒   ▒▒ ░ ░ ▒  ▒
+//   ░   ░     ░   ░ ░   ░  ░    ░   ░ ░     ░   ▒    ░ ░  ░
+//     ░       ░  ░      ░  ░          ░  ░      ░  ░   ░
+//                               ░                    ░
+
+/// More ranges than this are ignored and the whole body is sent, so a client can't make the server
+/// seek all over a file, or send the same bytes many times.
+const MAX_RANGES: usize = 16;
+
+/// `ByteRange` is a satisfiable range of a body, both ends inclusive like in `Content-Range`.
+///
+/// Properties:
+///
+/// * `start`: The offset of the first byte.
+/// * `end`: The offset of the last byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    start: u64,
+    end: u64
+}
+
+impl ByteRange {
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+    /// How many bytes the range covers.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+/// What a `Range` header asks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The header is missing, malformed or asks for too many ranges: send the whole body.
+    Full,
+    /// Send these ranges, sorted, with overlapping and adjacent ones merged.
+    Partial(Vec<ByteRange>),
+    /// None of the ranges is in the body: answer with `416 Range Not Satisfiable`.
+    Unsatisfiable
+}
+
+/// It parses the value of a `Range` header, e.g. `bytes=0-99, 200-, -50`, for a body of a known
+/// length. Only byte ranges are supported, other units get the whole body.
+///
+/// Arguments:
+///
+/// * `header_value`: The value of the `Range` header.
+/// * `length`: The length of the whole body.
+///
+/// Returns:
+///
+/// A RangeRequest
+pub fn parse_range(header_value: &str, length: u64) -> RangeRequest {
+    let specs: &str = match header_value.trim().split_once('=') {
+        Some((unit, specs)) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+        _ => return RangeRequest::Full
+    };
+    let specs: Vec<&str> = specs.split(',')
+        .map(|spec: &str| spec.trim())
+        .filter(|spec: &&str| !spec.is_empty())
+        .collect();
+    if specs.is_empty() || specs.len() > MAX_RANGES {
+        return RangeRequest::Full;
+    }
+    let mut ranges: Vec<ByteRange> = Vec::new();
+    for spec in specs {
+        let (first, last) = match spec.split_once('-') {
+            Some(bounds) => bounds,
+            None => return RangeRequest::Full
+        };
+        let range: Option<ByteRange> = match (first.trim(), last.trim()) {
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) => None,
+                Ok(suffix) if length > 0 => Some(ByteRange { start: length.saturating_sub(suffix), end: length - 1 }),
+                Ok(_) => None,
+                Err(_) => return RangeRequest::Full
+            },
+            (first, last) => {
+                let start: u64 = match first.parse::<u64>() {
+                    Ok(start) => start,
+                    Err(_) => return RangeRequest::Full
+                };
+                let end: u64 = match last {
+                    "" => u64::MAX,
+                    last => match last.parse::<u64>() {
+                        Ok(end) if end >= start => end,
+                        _ => return RangeRequest::Full
+                    }
+                };
+                (start < length).then(|| ByteRange { start, end: end.min(length - 1) })
+            }
+        };
+        ranges.extend(range);
+    }
+    if ranges.is_empty() {
+        return RangeRequest::Unsatisfiable;
+    }
+    ranges.sort_by_key(|range: &ByteRange| range.start);
+    let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => last.end = last.end.max(range.end),
+            _ => merged.push(range)
+        }
+    }
+    RangeRequest::Partial(merged)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::web::util::parsers::range_parser::{parse_range, ByteRange, RangeRequest};
+
+    fn partial(ranges: &[(u64, u64)]) -> RangeRequest {
+        RangeRequest::Partial(ranges.iter().map(|&(start, end)| ByteRange { start, end }).collect())
+    }
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), partial(&[(0, 99)]));
+        assert_eq!(parse_range("bytes=900-", 1000), partial(&[(900, 999)]));
+        assert_eq!(parse_range("bytes=-100", 1000), partial(&[(900, 999)]));
+        assert_eq!(parse_range("bytes=-5000", 1000), partial(&[(0, 999)]));
+        assert_eq!(parse_range("bytes=990-2000", 1000), partial(&[(990, 999)]));
+        assert_eq!(parse_range("bytes=500-599, 0-9, 5-20, 21-30", 1000), partial(&[(0, 30), (500, 599)]));
+        assert_eq!(parse_range("bytes=1000-", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=5-1", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("items=0-1", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=a-b", 1000), RangeRequest::Full);
+        assert_eq!(parse_range(&format!("bytes={}", vec!["0-0"; 17].join(",")), 1000), RangeRequest::Full);
+    }
+}
@@ -1,9 +1,10 @@
 
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read};
 use std::net::TcpStream;
+use std::time::{Duration, Instant};
 
 use crate::web::models::request::Request;
-use crate::web::models::response::Response;
+use crate::web::request_handling::timeouts::Timeouts;
 
 //  █     █░▓█████   ▄████  ▄▄▄▄    ██▓     ▄▄▄      ▓█████▄
 // ▓█░ █ ░█░▓█   ▀  ██▒ ▀█▒▓█████▄ ▓██▒    ▒████▄    ▒██▀ ██▌
@@ -20,9 +21,27 @@ use crate::web::models::response::Response;
 pub enum RequestParseError {
     NoMethod,
     NoPath,
-    NoProtocol
+    NoProtocol,
+    /// The path has a `..` segment, a malformed percent-encoding, or doesn't decode to UTF-8.
+    InvalidPath,
+    /// The client sent nothing before the idle or read timeout, or not the whole head or body
+    /// before the headers or body timeout.
+    TimedOut,
+    /// The connection failed while reading, e.g. it was reset.
+    ReadFailed,
+    /// The connection ended before the body announced by `Content-Length` arrived.
+    IncompleteBody,
+    /// The request line and headers are longer than `MAX_HEAD_SIZE`, or the body announced by
+    /// `Content-Length` is longer than `MAX_BODY_SIZE`. A request with too large a body is still
+    /// parsed and marked `Truncated`, so the error response can be negotiated.
+    TooLarge
 }
 
+/// A request extension marking a request that announced a body longer than `MAX_BODY_SIZE`. The request
+/// handler answers it with `RequestParseError::TooLarge` instead of handling it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncated;
+
 impl ToString for RequestParseError {
     fn to_string(&self) -> String {
         match self {
@@ -34,49 +53,265 @@ impl ToString for RequestParseError {
             },
             RequestParseError::NoMethod => {
                 "No method"
+            },
+            RequestParseError::InvalidPath => {
+                "Invalid path"
+            },
+            RequestParseError::TimedOut => {
+                "Timed out"
+            },
+            RequestParseError::ReadFailed => {
+                "Read failed"
+            },
+            RequestParseError::IncompleteBody => {
+                "Incomplete body"
+            },
+            RequestParseError::TooLarge => {
+                "Too large"
             }
         }.to_string()
     }
 }
 
-/// It reads a buffer from a TcpStream, parses it into a Request, creates a new empty Response, and
-/// returns a Transaction
+/// How long the request line and headers may be.
+pub const MAX_HEAD_SIZE: usize = 8 * 1024;
+/// How long a body may be. Longer bodies aren't read at all.
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// It reads a request from a TcpStream and parses it into a Request. The head is read until the
+/// blank line that ends it and the body until `Content-Length` bytes arrived, each read bounded by
+/// the timeouts: the first read waits up to the idle timeout the caller set on the socket, every
+/// later read up to the read timeout, the whole head has to arrive within the headers timeout and
+/// the whole body within the body timeout, so a client sending the request byte by byte can't hold
+/// on to the thread.
 ///
 /// Arguments:
 ///
 /// * `tcp_stream`: The TcpStream that we're reading from.
-/// * `buf`: [u8; 1024]
+/// * `timeouts`: The read, headers and body timeouts.
 ///
 /// Returns:
 ///
-/// A Transaction struct
-pub fn parse_request<'a>(mut tcp_stream: TcpStream, mut buf: [u8; 1024]) -> Result<Request, RequestParseError> {
-    tcp_stream.read(&mut buf).expect("TcpStream read failed");
-    let buf: Vec<u8> = buf.into_iter()
-        .filter(|byte: &u8|*byte != 13 && *byte != 0).collect::<Vec<u8>>();
-    // Checking for delimiting double \n between headers and body.
-    let mut previous_was_newline: bool = false;
-    let mut index_for_split: usize = buf.len();
-    for (index, val) in buf.iter().enumerate() {
-        if *val == 10 {
-            if previous_was_newline {
-                index_for_split = index;
-                break;
+/// The parsed Request, marked `Truncated` if it announced a body longer than `MAX_BODY_SIZE`.
+pub fn parse_request(mut tcp_stream: TcpStream, timeouts: &Timeouts) -> Result<Request, RequestParseError> {
+    let mut received: Vec<u8> = Vec::with_capacity(1024);
+    // The headers deadline starts with the first bytes, before that the idle timeout applies.
+    let mut deadline: Option<Instant> = None;
+    let (head_end, body_start): (usize, usize) = loop {
+        // Empty lines before the request line are skipped (RFC 9112 section 2.2), e.g. a CRLF left
+        // over from the previous request.
+        let blank: usize = received.iter().take_while(|byte: &&u8| matches!(**byte, b'\r' | b'\n')).count();
+        received.drain(..blank);
+        if let Some(head_end) = find_head_end(&received) {
+            break head_end;
+        }
+        if received.len() == MAX_HEAD_SIZE {
+            return Err(RequestParseError::TooLarge);
+        }
+        if read_into(&mut tcp_stream, &mut received, MAX_HEAD_SIZE, deadline, timeouts.get_read())? == 0 {
+            // The client stopped sending, what arrived is all the head there is.
+            if received.is_empty() {
+                return Err(RequestParseError::ReadFailed);
             }
-            previous_was_newline = true;
-        } else {
-            previous_was_newline = false;
+            break (received.len(), received.len());
+        }
+        deadline.get_or_insert_with(|| Instant::now() + timeouts.get_headers());
+    };
+    let head: Vec<u8> = received[..head_end].iter()
+        .filter(|byte: &&u8| **byte != b'\r' && **byte != 0).copied().collect::<Vec<u8>>();
+    let mut req: Request = Request::new_without_stream(&head, &[])?;
+    let announced: Option<usize> = req.get_header("Content-Length")
+        .and_then(|length: &str| length.trim().parse::<usize>().ok());
+    if announced.is_some_and(|announced: usize| announced > MAX_BODY_SIZE) {
+        // The body isn't read, the request is only answered with the error response.
+        req.extensions_mut().insert(Truncated);
+        req.set_stream(tcp_stream);
+        return Ok(req);
+    }
+    let wanted: usize = body_start + announced.unwrap_or(0);
+    received.reserve(wanted.saturating_sub(received.len()));
+    let deadline: Instant = Instant::now() + timeouts.get_body();
+    while received.len() < wanted {
+        if read_into(&mut tcp_stream, &mut received, wanted, Some(deadline), timeouts.get_read())? == 0 {
+            return Err(RequestParseError::IncompleteBody);
         }
     }
-    let (headers, body): (&[u8], &[u8]) = buf.split_at(index_for_split);
-    // Removing trailing and prefixing newlines.
-    let (headers, body): (&[u8], &[u8]) = (&headers[..headers.len()-1], &body[1..]);
-    if log::log_enabled!(log::Level::Debug) {
-        log::debug!("\nHeaders: \n{:#?},\nBody: \n{:#?},\n",
-            String::from_utf8_lossy(headers),
-            String::from_utf8_lossy(body),
-        );
+    received.truncate(wanted.max(body_start));
+    req.set_body(received.split_off(body_start));
+    req.set_stream(tcp_stream);
+    Ok(req)
+}
+
+/// It finds the blank line that ends the head of a request.
+///
+/// Returns:
+///
+/// The index the head ends at and the index the body starts at, if the head is complete.
+fn find_head_end(received: &[u8]) -> Option<(usize, usize)> {
+    received.iter().enumerate()
+        .filter(|(_, byte): &(usize, &u8)| **byte == b'\n')
+        .find_map(|(index, _): (usize, &u8)| {
+            if received[index + 1..].starts_with(b"\n") {
+                Some((index, index + 2))
+            } else if received[index + 1..].starts_with(b"\r\n") {
+                Some((index, index + 3))
+            } else {
+                None
+            }
+        })
+}
+
+/// It does a single read from the stream, appending to what was received so far.
+///
+/// Arguments:
+///
+/// * `tcp_stream`: The TcpStream that we're reading from.
+/// * `received`: What was received so far.
+/// * `limit`: How long `received` may grow.
+/// * `deadline`: When the read has to be done by, on top of the read timeout.
+/// * `read_timeout`: How long the read may block.
+///
+/// Returns:
+///
+/// How many bytes were read, 0 once the client stopped sending.
+fn read_into(tcp_stream: &mut TcpStream, received: &mut Vec<u8>, limit: usize, deadline: Option<Instant>,
+             read_timeout: Duration) -> Result<usize, RequestParseError> {
+    if let Some(deadline) = deadline {
+        let remaining: Duration = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(RequestParseError::TimedOut);
+        }
+        let _ = tcp_stream.set_read_timeout(Some(remaining.min(read_timeout)));
+    }
+    let mut buf: [u8; 1024] = [0; 1024];
+    let wanted: usize = buf.len().min(limit - received.len());
+    match tcp_stream.read(&mut buf[..wanted]) {
+        Ok(read) => {
+            received.extend_from_slice(&buf[..read]);
+            Ok(read)
+        },
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Err(RequestParseError::TimedOut),
+        Err(_) => Err(RequestParseError::ReadFailed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::thread;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+    use crate::web::models::request::Request;
+    use crate::web::request_handling::timeouts::Timeouts;
+    use crate::web::util::parsers::request_parser::{parse_request, RequestParseError, Truncated, MAX_BODY_SIZE, MAX_HEAD_SIZE};
+
+    fn connection() -> (TcpStream, TcpStream) {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client: TcpStream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn reads_requests_sent_in_pieces() {
+        let (mut client, server) = connection();
+        let sender: JoinHandle<TcpStream> = thread::spawn(move || {
+            for piece in ["POST /users HTTP/1.1\r\nContent-", "Length: 11\r\n\r\nhello", " world"] {
+                client.write_all(piece.as_bytes()).unwrap();
+                thread::sleep(Duration::from_millis(20));
+            }
+            client
+        });
+        let req: Request = parse_request(server, &Timeouts::new()).unwrap();
+        assert_eq!(req.request_line_data().path(), "/users");
+        assert_eq!(req.get_header("Content-Length"), Some("11"));
+        assert_eq!(req.body().as_slice(), b"hello world");
+        drop(sender.join());
+    }
+
+    #[test]
+    fn compares_bodies_with_announced_length() {
+        // Bodies longer than a single read arrive whole.
+        let (mut client, server) = connection();
+        let body: Vec<u8> = vec![b'x'; 5000];
+        client.write_all(format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", body.len()).as_bytes()).unwrap();
+        client.write_all(&body).unwrap();
+        let req: Request = parse_request(server, &Timeouts::new()).unwrap();
+        assert_eq!(req.body(), &body);
+        assert!(req.extension::<Truncated>().is_none());
+
+        // A body ending before the announced length is rejected instead of handled truncated.
+        let (mut client, server) = connection();
+        client.write_all(b"POST / HTTP/1.1\r\nContent-Length: 100\r\n\r\nshort").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert!(matches!(parse_request(server, &Timeouts::new()), Err(RequestParseError::IncompleteBody)));
+
+        // Bodies announced longer than the limit aren't read.
+        let (mut client, server) = connection();
+        client.write_all(format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1).as_bytes()).unwrap();
+        let req: Request = parse_request(server, &Timeouts::new()).unwrap();
+        assert!(req.extension::<Truncated>().is_some());
+        assert!(req.body().is_empty());
+
+        // Heads longer than the limit are rejected.
+        let (mut client, server) = connection();
+        client.write_all(format!("GET / HTTP/1.1\r\nX-Filler: {}\r\n\r\n", "x".repeat(MAX_HEAD_SIZE)).as_bytes()).unwrap();
+        assert!(matches!(parse_request(server, &Timeouts::new()), Err(RequestParseError::TooLarge)));
+    }
+
+    #[test]
+    fn skips_empty_lines_before_the_request_line() {
+        let (mut client, server) = connection();
+        client.write_all(b"\r\n\r\nGET / HTTP/1.1\r\n\r\n").unwrap();
+        let req: Request = parse_request(server, &Timeouts::new()).unwrap();
+        assert_eq!(req.request_line_data().method(), "GET");
+        assert_eq!(req.request_line_data().path(), "/");
+
+        // A client sending only empty lines has sent no request.
+        let (mut client, server) = connection();
+        client.write_all(b"\n\n").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert!(matches!(parse_request(server, &Timeouts::new()), Err(RequestParseError::ReadFailed)));
+        assert!(matches!(Request::new_without_stream(b"", b""), Err(RequestParseError::NoMethod)));
+    }
+
+    #[test]
+    fn times_out_slow_heads() {
+        let (mut client, server) = connection();
+        let timeouts: Timeouts = Timeouts::new()
+            .read(Duration::from_millis(200))
+            .headers(Duration::from_millis(100));
+        let sender: JoinHandle<()> = thread::spawn(move || {
+            // Every byte comes well within the read timeout, but the head never completes in time.
+            for byte in b"GET / HTTP/1.1\r\nHost: example.com\r\n".iter() {
+                if client.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+        assert!(matches!(parse_request(server, &timeouts), Err(RequestParseError::TimedOut)));
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn times_out_slow_bodies() {
+        let (mut client, server) = connection();
+        let timeouts: Timeouts = Timeouts::new()
+            .read(Duration::from_millis(200))
+            .body(Duration::from_millis(100));
+        client.write_all(b"POST / HTTP/1.1\r\nContent-Length: 1000\r\n\r\n").unwrap();
+        let sender: JoinHandle<()> = thread::spawn(move || {
+            // Every byte comes well within the read timeout, but the body never completes in time.
+            for _ in 0..50 {
+                if client.write_all(b"x").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+        });
+        assert!(matches!(parse_request(server, &timeouts), Err(RequestParseError::TimedOut)));
+        sender.join().unwrap();
     }
-    let tcp_clone: std::io::Result<TcpStream> = tcp_stream.try_clone();
-    Request::new(headers, body, tcp_stream)
 }